@@ -1,6 +1,6 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 
-use ethereum_types::{Address, U256};
+use ethereum_types::{Address, H256, U256};
 use ethsign::Signature;
 use ethsign_crypto::Keccak256;
 use rlp::RlpStream;
@@ -11,6 +11,9 @@ mod cryptde;
 
 pub use bip32::Bip32ECKeyPair;
 
+const ACCESS_LIST_TX_TYPE: u8 = 1;
+const EIP1559_TX_TYPE: u8 = 2;
+
 /// Description of a Transaction, pending or in the chain.
 #[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
 pub struct RawTransaction {
@@ -30,26 +33,84 @@ pub struct RawTransaction {
     pub data: Vec<u8>,
 }
 
+/// Storage slots of a contract accessed by the transaction (EIP-2930)
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AccessListItem {
+    /// Contract address
+    pub address: Address,
+    /// Accessed storage slots
+    #[serde(rename = "storageKeys")]
+    pub storage_keys: Vec<H256>,
+}
+
+pub type AccessList = Vec<AccessListItem>;
+
+/// Transaction with an access list, type 1 (EIP-2930).
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AccessListTransaction {
+    /// Nonce
+    pub nonce: U256,
+    /// Recipient (None when contract creation)
+    pub to: Option<Address>,
+    /// Transfered value
+    pub value: U256,
+    /// Gas Price
+    #[serde(rename = "gasPrice")]
+    pub gas_price: U256,
+    /// Gas limit
+    #[serde(rename = "gasLimit")]
+    pub gas_limit: U256,
+    /// Input data
+    pub data: Vec<u8>,
+    /// Access list
+    #[serde(rename = "accessList", default)]
+    pub access_list: AccessList,
+}
+
+/// Transaction with a priority fee on top of the base fee of the block, type 2 (EIP-1559).
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Eip1559Transaction {
+    /// Nonce
+    pub nonce: U256,
+    /// Recipient (None when contract creation)
+    pub to: Option<Address>,
+    /// Transfered value
+    pub value: U256,
+    /// Max fee per gas paid to the miner
+    #[serde(rename = "maxPriorityFeePerGas")]
+    pub max_priority_fee_per_gas: U256,
+    /// Max fee per gas including the base fee
+    #[serde(rename = "maxFeePerGas")]
+    pub max_fee_per_gas: U256,
+    /// Gas limit
+    #[serde(rename = "gasLimit")]
+    pub gas_limit: U256,
+    /// Input data
+    pub data: Vec<u8>,
+    /// Access list
+    #[serde(rename = "accessList", default)]
+    pub access_list: AccessList,
+}
+
 impl RawTransaction {
-    /// Signs and returns the RLP-encoded transaction
-    pub fn sign(&self, bip32ec_keypair: &Bip32ECKeyPair, chain_id: u8) -> Vec<u8> {
+    /// Signs and returns the RLP-encoded transaction, `v` is replay protected (EIP-155)
+    pub fn sign(&self, bip32ec_keypair: &Bip32ECKeyPair, chain_id: u64) -> Vec<u8> {
         let hash = self.tx_hash(chain_id);
-        let sig = ecdsa_sign(&hash, bip32ec_keypair, chain_id);
+        let sig = ecdsa_sign(&hash, bip32ec_keypair);
         let mut tx = RlpStream::new();
         tx.begin_unbounded_list();
         self.encode(&mut tx);
-        tx.append(&sig.v);
-        tx.append(&sig.r.to_vec());
-        tx.append(&sig.s.to_vec());
+        tx.append(&(u64::from(sig.v) + chain_id * 2 + 35));
+        append_signature(&mut tx, &sig);
         tx.complete_unbounded_list();
         tx.out()
     }
 
-    fn tx_hash(&self, chain_id: u8) -> Vec<u8> {
+    fn tx_hash(&self, chain_id: u64) -> Vec<u8> {
         let mut hash = RlpStream::new();
         hash.begin_unbounded_list();
         self.encode(&mut hash);
-        hash.append(&vec![chain_id]);
+        hash.append(&chain_id_bytes(chain_id));
         hash.append(&U256::zero());
         hash.append(&U256::zero());
         hash.complete_unbounded_list();
@@ -60,23 +121,109 @@ impl RawTransaction {
         s.append(&self.nonce);
         s.append(&self.gas_price);
         s.append(&self.gas_limit);
-        if let Some(ref t) = self.to {
-            s.append(t);
-        } else {
-            s.append(&vec![]);
-        }
+        append_to(s, &self.to);
         s.append(&self.value);
         s.append(&self.data);
     }
 }
 
-fn ecdsa_sign(hash: &[u8], bip32ec_keypair: &Bip32ECKeyPair, chain_id: u8) -> Signature {
+impl AccessListTransaction {
+    /// Signs and returns the transaction type followed by the RLP-encoded transaction
+    pub fn sign(&self, bip32ec_keypair: &Bip32ECKeyPair, chain_id: u64) -> Vec<u8> {
+        sign_typed(ACCESS_LIST_TX_TYPE, bip32ec_keypair, |s| {
+            s.append(&chain_id);
+            s.append(&self.nonce);
+            s.append(&self.gas_price);
+            s.append(&self.gas_limit);
+            append_to(s, &self.to);
+            s.append(&self.value);
+            s.append(&self.data);
+            append_access_list(s, &self.access_list);
+        })
+    }
+}
+
+impl Eip1559Transaction {
+    /// Signs and returns the transaction type followed by the RLP-encoded transaction
+    pub fn sign(&self, bip32ec_keypair: &Bip32ECKeyPair, chain_id: u64) -> Vec<u8> {
+        sign_typed(EIP1559_TX_TYPE, bip32ec_keypair, |s| {
+            s.append(&chain_id);
+            s.append(&self.nonce);
+            s.append(&self.max_priority_fee_per_gas);
+            s.append(&self.max_fee_per_gas);
+            s.append(&self.gas_limit);
+            append_to(s, &self.to);
+            s.append(&self.value);
+            s.append(&self.data);
+            append_access_list(s, &self.access_list);
+        })
+    }
+}
+
+// typed transactions sign the type with the payload, `v` is the y-parity of the signature
+fn sign_typed<F>(tx_type: u8, bip32ec_keypair: &Bip32ECKeyPair, encode: F) -> Vec<u8>
+where
+    F: Fn(&mut RlpStream),
+{
+    let mut payload = RlpStream::new();
+    payload.begin_unbounded_list();
+    encode(&mut payload);
+    payload.complete_unbounded_list();
+    let hash = typed_envelope(tx_type, payload.out()).keccak256();
+
+    let sig = ecdsa_sign(&hash, bip32ec_keypair);
+    let mut tx = RlpStream::new();
+    tx.begin_unbounded_list();
+    encode(&mut tx);
+    tx.append(&sig.v);
+    append_signature(&mut tx, &sig);
+    tx.complete_unbounded_list();
+    typed_envelope(tx_type, tx.out())
+}
+
+fn typed_envelope(tx_type: u8, payload: Vec<u8>) -> Vec<u8> {
+    let mut envelope = Vec::with_capacity(payload.len() + 1);
+    envelope.push(tx_type);
+    envelope.extend(payload);
+    envelope
+}
+
+fn append_to(s: &mut RlpStream, to: &Option<Address>) {
+    if let Some(ref t) = to {
+        s.append(t);
+    } else {
+        s.append(&vec![]);
+    }
+}
+
+fn append_access_list(s: &mut RlpStream, access_list: &[AccessListItem]) {
+    s.begin_list(access_list.len());
+    for item in access_list {
+        s.begin_list(2);
+        s.append(&item.address);
+        s.append_list::<H256, _>(&item.storage_keys);
+    }
+}
+
+// r and s are integers, leading zeros are not encoded
+fn append_signature(s: &mut RlpStream, sig: &Signature) {
+    s.append(&U256::from_big_endian(&sig.r));
+    s.append(&U256::from_big_endian(&sig.s));
+}
+
+// big-endian bytes without leading zeros, zero chain id is a single zero byte as it was signed before
+fn chain_id_bytes(chain_id: u64) -> Vec<u8> {
+    let bytes = chain_id.to_be_bytes();
+    let first = bytes
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(bytes.len() - 1);
+    bytes[first..].to_vec()
+}
+
+fn ecdsa_sign(hash: &[u8], bip32ec_keypair: &Bip32ECKeyPair) -> Signature {
     match bip32ec_keypair.sign(hash) {
-        Ok(s) => Signature {
-            v: s.v + chain_id * 2 + 35,
-            r: s.r,
-            s: s.s,
-        },
+        Ok(s) => s,
         Err(e) => panic!("{:?}", e),
     }
 }
@@ -84,8 +231,6 @@ fn ecdsa_sign(hash: &[u8], bip32ec_keypair: &Bip32ECKeyPair, chain_id: u8) -> Si
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::blockchain::bip32::Bip32ECKeyPair;
-    use ethereum_types::H256;
 
     #[derive(Deserialize)]
     struct Signing {
@@ -102,14 +247,12 @@ mod tests {
         ]"#);
 
         let txs: Vec<(RawTransaction, Signing)> = serde_json::from_str(&text_txs_json).unwrap();
-        let chain_id = 0u8;
+        let chain_id = 0u64;
         for (tx, signed) in txs.into_iter() {
             assert_eq!(
                 signed.signed,
                 tx.sign(
-                    &Wallet::from(
-                        Bip32ECKeyPair::from_raw_secret(&signed.private_key.0.as_ref()).unwrap()
-                    ),
+                    &Bip32ECKeyPair::from_raw_secret(&signed.private_key.0.as_ref()).unwrap(),
                     chain_id
                 )
             );
@@ -125,14 +268,12 @@ mod tests {
         ]"#);
 
         let txs: Vec<(RawTransaction, Signing)> = serde_json::from_str(&text_txs_json).unwrap();
-        let chain_id = 3u8;
+        let chain_id = 3u64;
         for (tx, signed) in txs.into_iter() {
             assert_eq!(
                 signed.signed,
                 tx.sign(
-                    &Wallet::from(
-                        Bip32ECKeyPair::from_raw_secret(&signed.private_key.0.as_ref()).unwrap()
-                    ),
+                    &Bip32ECKeyPair::from_raw_secret(&signed.private_key.0.as_ref()).unwrap(),
                     chain_id
                 )
             );
@@ -147,14 +288,12 @@ mod tests {
         ]"#);
 
         let txs: Vec<(RawTransaction, Signing)> = serde_json::from_str(&txt_txs_json).unwrap();
-        let chain_id = 3u8;
+        let chain_id = 3u64;
         for (tx, signed) in txs.into_iter() {
             assert_eq!(
                 signed.signed,
                 tx.sign(
-                    &Wallet::from(
-                        Bip32ECKeyPair::from_raw_secret(&signed.private_key.0.as_ref()).unwrap()
-                    ),
+                    &Bip32ECKeyPair::from_raw_secret(&signed.private_key.0.as_ref()).unwrap(),
                     chain_id
                 )
             );
@@ -169,14 +308,127 @@ mod tests {
         ]"#);
 
         let txs: Vec<(RawTransaction, Signing)> = serde_json::from_str(&txt_txs_json).unwrap();
-        let chain_id = 1u8;
+        let chain_id = 1u64;
+        for (tx, signed) in txs.into_iter() {
+            assert_eq!(
+                signed.signed,
+                tx.sign(
+                    &Bip32ECKeyPair::from_raw_secret(&signed.private_key.0.as_ref()).unwrap(),
+                    chain_id
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_signs_transaction_with_large_chain_id() {
+        let txt_txs_json = String::from(r#"[
+            [{"nonce": "0x9", "gasPrice": "0x4a817c800", "gasLimit": "0x5208", "to": "0x3535353535353535353535353535353535353535", "value": "0xde0b6b3a7640000", "data": []}, {"private_key": "0x4646464646464646464646464646464646464646464646464646464646464646", "signed": [248, 110, 9, 133, 4, 168, 23, 200, 0, 130, 82, 8, 148, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 136, 13, 224, 182, 179, 167, 100, 0, 0, 128, 130, 10, 150, 160, 17, 209, 240, 185, 222, 85, 74, 217, 230, 144, 187, 131, 85, 80, 112, 7, 115, 27, 116, 30, 35, 46, 203, 13, 193, 131, 21, 76, 16, 199, 120, 117, 160, 58, 75, 50, 96, 124, 140, 34, 135, 232, 42, 232, 194, 163, 52, 216, 65, 43, 175, 21, 229, 46, 226, 92, 83, 23, 98, 220, 52, 37, 42, 19, 101]}],
+            [{"nonce": "0x0", "gasPrice": "0x3b9aca00", "gasLimit": "0x0f4240", "to": "0xcd6C588E005032dd882CD43Bf53a32129BE81302", "value": "0x0", "data": [169, 5, 156, 187, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 71, 251, 134, 113, 219, 131, 0, 141, 56, 44, 46, 110, 166, 127, 163, 119, 55, 140, 12, 234, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 99]}, {"private_key": "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318", "signed": [248, 171, 128, 132, 59, 154, 202, 0, 131, 15, 66, 64, 148, 205, 108, 88, 142, 0, 80, 50, 221, 136, 44, 212, 59, 245, 58, 50, 18, 155, 232, 19, 2, 128, 184, 68, 169, 5, 156, 187, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 71, 251, 134, 113, 219, 131, 0, 141, 56, 44, 46, 110, 166, 127, 163, 119, 55, 140, 12, 234, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 99, 130, 10, 149, 160, 214, 68, 87, 166, 13, 17, 100, 26, 0, 191, 184, 164, 115, 118, 93, 236, 36, 128, 163, 180, 190, 232, 94, 98, 50, 134, 252, 175, 84, 124, 222, 37, 160, 23, 38, 85, 50, 15, 115, 211, 199, 98, 40, 199, 45, 175, 53, 139, 179, 47, 197, 51, 150, 11, 212, 78, 4, 124, 138, 76, 7, 184, 229, 187, 228]}]
+        ]"#);
+
+        let txs: Vec<(RawTransaction, Signing)> = serde_json::from_str(&txt_txs_json).unwrap();
+        let chain_id = 1337u64;
+        for (tx, signed) in txs.into_iter() {
+            assert_eq!(
+                signed.signed,
+                tx.sign(
+                    &Bip32ECKeyPair::from_raw_secret(&signed.private_key.0.as_ref()).unwrap(),
+                    chain_id
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_signs_access_list_transaction_mainnet() {
+        let txt_txs_json = String::from(r#"[
+            [{"nonce": "0x9", "gasPrice": "0x4a817c800", "gasLimit": "0x5208", "to": "0x3535353535353535353535353535353535353535", "value": "0xde0b6b3a7640000", "data": [], "accessList": []}, {"private_key": "0x4646464646464646464646464646464646464646464646464646464646464646", "signed": [1, 248, 110, 1, 9, 133, 4, 168, 23, 200, 0, 130, 82, 8, 148, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 136, 13, 224, 182, 179, 167, 100, 0, 0, 128, 192, 1, 160, 165, 95, 106, 134, 22, 227, 214, 210, 240, 158, 233, 69, 205, 173, 42, 33, 167, 211, 87, 34, 95, 207, 196, 109, 249, 232, 66, 20, 32, 149, 79, 67, 160, 29, 123, 216, 195, 137, 47, 66, 113, 243, 151, 201, 30, 108, 66, 22, 251, 66, 132, 188, 117, 200, 211, 32, 185, 82, 189, 176, 105, 170, 168, 18, 14]}]
+        ]"#);
+
+        let txs: Vec<(AccessListTransaction, Signing)> = serde_json::from_str(&txt_txs_json).unwrap();
+        let chain_id = 1u64;
+        for (tx, signed) in txs.into_iter() {
+            assert_eq!(
+                signed.signed,
+                tx.sign(
+                    &Bip32ECKeyPair::from_raw_secret(&signed.private_key.0.as_ref()).unwrap(),
+                    chain_id
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_signs_access_list_transaction_ropsten() {
+        let txt_txs_json = String::from(r#"[
+            [{"nonce": "0x0", "gasPrice": "0x3b9aca00", "gasLimit": "0x0f4240", "to": "0xcd6C588E005032dd882CD43Bf53a32129BE81302", "value": "0x0", "data": [169, 5, 156, 187, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 71, 251, 134, 113, 219, 131, 0, 141, 56, 44, 46, 110, 166, 127, 163, 119, 55, 140, 12, 234, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 99], "accessList": [{"address": "0xcd6C588E005032dd882CD43Bf53a32129BE81302", "storageKeys": ["0x0000000000000000000000000000000000000000000000000000000000000001", "0x0000000000000000000000000000000000000000000000000000000000000007"]}]}, {"private_key": "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318", "signed": [1, 249, 1, 7, 3, 128, 132, 59, 154, 202, 0, 131, 15, 66, 64, 148, 205, 108, 88, 142, 0, 80, 50, 221, 136, 44, 212, 59, 245, 58, 50, 18, 155, 232, 19, 2, 128, 184, 68, 169, 5, 156, 187, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 71, 251, 134, 113, 219, 131, 0, 141, 56, 44, 46, 110, 166, 127, 163, 119, 55, 140, 12, 234, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 99, 248, 91, 248, 89, 148, 205, 108, 88, 142, 0, 80, 50, 221, 136, 44, 212, 59, 245, 58, 50, 18, 155, 232, 19, 2, 248, 66, 160, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 160, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7, 128, 160, 158, 196, 46, 121, 115, 134, 2, 26, 226, 87, 21, 212, 65, 161, 207, 91, 41, 78, 199, 150, 117, 188, 132, 44, 63, 168, 249, 16, 165, 179, 86, 172, 160, 110, 203, 158, 210, 255, 108, 59, 252, 121, 45, 83, 87, 213, 128, 52, 147, 194, 221, 244, 56, 129, 6, 56, 144, 62, 109, 102, 144, 102, 3, 250, 202]}]
+        ]"#);
+
+        let txs: Vec<(AccessListTransaction, Signing)> = serde_json::from_str(&txt_txs_json).unwrap();
+        let chain_id = 3u64;
+        for (tx, signed) in txs.into_iter() {
+            assert_eq!(
+                signed.signed,
+                tx.sign(
+                    &Bip32ECKeyPair::from_raw_secret(&signed.private_key.0.as_ref()).unwrap(),
+                    chain_id
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_signs_eip1559_transaction_mainnet() {
+        let txt_txs_json = String::from(r#"[
+            [{"nonce": "0x9", "maxPriorityFeePerGas": "0x3b9aca00", "maxFeePerGas": "0x4a817c800", "gasLimit": "0x5208", "to": "0x3535353535353535353535353535353535353535", "value": "0xde0b6b3a7640000", "data": [], "accessList": []}, {"private_key": "0x4646464646464646464646464646464646464646464646464646464646464646", "signed": [2, 248, 115, 1, 9, 132, 59, 154, 202, 0, 133, 4, 168, 23, 200, 0, 130, 82, 8, 148, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 53, 136, 13, 224, 182, 179, 167, 100, 0, 0, 128, 192, 128, 160, 78, 135, 206, 216, 180, 125, 128, 28, 151, 156, 107, 170, 82, 187, 215, 139, 66, 201, 219, 37, 21, 201, 209, 244, 115, 224, 111, 101, 212, 154, 170, 144, 160, 35, 87, 103, 21, 23, 197, 149, 68, 235, 217, 80, 18, 209, 152, 140, 16, 34, 146, 235, 87, 12, 200, 64, 172, 154, 247, 43, 180, 197, 46, 94, 221]}]
+        ]"#);
+
+        let txs: Vec<(Eip1559Transaction, Signing)> = serde_json::from_str(&txt_txs_json).unwrap();
+        let chain_id = 1u64;
+        for (tx, signed) in txs.into_iter() {
+            assert_eq!(
+                signed.signed,
+                tx.sign(
+                    &Bip32ECKeyPair::from_raw_secret(&signed.private_key.0.as_ref()).unwrap(),
+                    chain_id
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_signs_eip1559_transaction_with_large_chain_id() {
+        let txt_txs_json = String::from(r#"[
+            [{"nonce": "0x0", "maxPriorityFeePerGas": "0x3b9aca00", "maxFeePerGas": "0x9502f9000", "gasLimit": "0x0f4240", "to": "0xcd6C588E005032dd882CD43Bf53a32129BE81302", "value": "0x0", "data": [169, 5, 156, 187, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 71, 251, 134, 113, 219, 131, 0, 141, 56, 44, 46, 110, 166, 127, 163, 119, 55, 140, 12, 234, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 99], "accessList": [{"address": "0xcd6C588E005032dd882CD43Bf53a32129BE81302", "storageKeys": ["0x0000000000000000000000000000000000000000000000000000000000000001", "0x0000000000000000000000000000000000000000000000000000000000000007"]}]}, {"private_key": "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318", "signed": [2, 249, 1, 15, 130, 5, 57, 128, 132, 59, 154, 202, 0, 133, 9, 80, 47, 144, 0, 131, 15, 66, 64, 148, 205, 108, 88, 142, 0, 80, 50, 221, 136, 44, 212, 59, 245, 58, 50, 18, 155, 232, 19, 2, 128, 184, 68, 169, 5, 156, 187, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 71, 251, 134, 113, 219, 131, 0, 141, 56, 44, 46, 110, 166, 127, 163, 119, 55, 140, 12, 234, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 99, 248, 91, 248, 89, 148, 205, 108, 88, 142, 0, 80, 50, 221, 136, 44, 212, 59, 245, 58, 50, 18, 155, 232, 19, 2, 248, 66, 160, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 160, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7, 128, 160, 190, 9, 180, 180, 222, 131, 245, 241, 174, 211, 171, 205, 167, 6, 178, 235, 227, 27, 70, 27, 187, 25, 182, 18, 57, 46, 152, 160, 32, 203, 106, 57, 160, 59, 65, 87, 254, 187, 216, 88, 204, 4, 105, 236, 144, 114, 87, 139, 194, 161, 232, 18, 171, 180, 92, 199, 219, 95, 44, 89, 40, 192, 225, 79, 244]}]
+        ]"#);
+
+        let txs: Vec<(Eip1559Transaction, Signing)> = serde_json::from_str(&txt_txs_json).unwrap();
+        let chain_id = 1337u64;
+        for (tx, signed) in txs.into_iter() {
+            assert_eq!(
+                signed.signed,
+                tx.sign(
+                    &Bip32ECKeyPair::from_raw_secret(&signed.private_key.0.as_ref()).unwrap(),
+                    chain_id
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_signs_eip1559_contract_creation_goerli() {
+        let txt_txs_json = String::from(r#"[
+            [{"nonce": "0x1", "maxPriorityFeePerGas": "0x0", "maxFeePerGas": "0x3b9aca00", "gasLimit": "0x2710", "to": null, "value": "0x0", "data": [127, 116, 101, 115, 116, 50, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 96, 0, 87], "accessList": []}, {"private_key": "0x4646464646464646464646464646464646464646464646464646464646464646", "signed": [2, 248, 118, 5, 1, 128, 132, 59, 154, 202, 0, 130, 39, 16, 128, 128, 164, 127, 116, 101, 115, 116, 50, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 96, 0, 87, 192, 128, 160, 162, 59, 55, 254, 138, 208, 240, 92, 242, 31, 235, 86, 131, 4, 42, 190, 47, 249, 74, 91, 60, 123, 134, 187, 98, 221, 160, 164, 146, 31, 97, 168, 160, 32, 203, 193, 223, 110, 48, 96, 192, 13, 188, 159, 227, 114, 166, 36, 151, 128, 237, 36, 68, 103, 87, 111, 24, 204, 230, 101, 16, 246, 228, 13, 137]}]
+        ]"#);
+
+        let txs: Vec<(Eip1559Transaction, Signing)> = serde_json::from_str(&txt_txs_json).unwrap();
+        let chain_id = 5u64;
         for (tx, signed) in txs.into_iter() {
             assert_eq!(
                 signed.signed,
                 tx.sign(
-                    &Wallet::from(
-                        Bip32ECKeyPair::from_raw_secret(&signed.private_key.0.as_ref()).unwrap()
-                    ),
+                    &Bip32ECKeyPair::from_raw_secret(&signed.private_key.0.as_ref()).unwrap(),
                     chain_id
                 )
             );
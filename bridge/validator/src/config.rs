@@ -1,124 +1,403 @@
 use primitives::{crypto::Pair, sr25519};
 use rustc_hex::FromHex;
-use web3::types::{Address, H256};
+use web3::types::Address;
 
 use raw_transaction_builder::Bip32ECKeyPair;
 
-use std::env;
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::{env, fs};
 
+const DEFAULT_CONFIG_PATH: &str = "validator.toml";
 const DEFAULT_GAS_PRICE: u64 = 24_000_000_000;
 const DEFAULT_GAS: u64 = 5_000_000;
+const DEFAULT_MIN_GAS_PRICE: u64 = 1_000_000_000;
+const DEFAULT_MAX_GAS_PRICE: u64 = 200_000_000_000;
+const DEFAULT_GAS_MARGIN_PERCENT: u64 = 20;
+const DEFAULT_FEE_HISTORY_BLOCKS: u64 = 20;
+const DEFAULT_ETH_CONFIRMATIONS: u64 = 12;
+const DEFAULT_STORAGE_PATH: &str = "./validator_db";
+const REDACTED: &str = "<redacted>";
+
+// sections of the config file and prefixes of the environment variables overriding their keys,
+// e.g. `chain_id` of `[ethereum]` is overridden by ETH_CHAIN_ID
+const SECTIONS: [(&str, &str); 3] = [
+    ("ethereum", "ETH_"),
+    ("substrate", "SUB_"),
+    ("storage", "STORAGE_"),
+];
+
+const VARIABLES: [&str; 16] = [
+    "ETH_API_URL",
+    "ETH_CHAIN_ID",
+    "ETH_VALIDATOR_ADDRESS",
+    "ETH_VALIDATOR_PRIVATE_KEY",
+    "ETH_CONTRACT_ADDRESS",
+    "ETH_GAS_PRICE",
+    "ETH_GAS_PRICE_STRATEGY",
+    "ETH_MIN_GAS_PRICE",
+    "ETH_MAX_GAS_PRICE",
+    "ETH_FEE_HISTORY_BLOCKS",
+    "ETH_GAS",
+    "ETH_GAS_MARGIN_PERCENT",
+    "ETH_CONFIRMATIONS",
+    "SUB_API_URL",
+    "SUB_VALIDATOR_MNEMONIC_PHRASE",
+    "STORAGE_PATH",
+];
+
+/// how the gas price of the validator transactions is chosen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GasPriceStrategy {
+    // ETH_GAS_PRICE
+    Fixed,
+    // eth_gasPrice of the node
+    Node,
+    // base fee and priority fees of recent blocks
+    FeeHistory,
+}
+
+impl Default for GasPriceStrategy {
+    fn default() -> Self {
+        GasPriceStrategy::Node
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Config {
-    pub eth_api_url: String,
+    // endpoints in the order of preference
+    pub eth_api_urls: Vec<String>,
+    pub eth_chain_id: u64,
     pub eth_validator_address: Address,
     pub eth_validator_private_key: String,
     pub eth_contract_address: Address,
-    pub eth_relay_message_hash: H256,
-    pub eth_approved_relay_message_hash: H256,
-    pub eth_withdraw_message_hash: H256,
     pub eth_gas_price: u64,
+    pub eth_gas_price_strategy: GasPriceStrategy,
+    pub eth_min_gas_price: u64,
+    pub eth_max_gas_price: u64,
+    pub eth_fee_history_blocks: u64,
+    // upper limit of the estimated gas
     pub eth_gas: u64,
-    pub sub_api_url: String,
+    pub eth_gas_margin_percent: u64,
+    pub eth_confirmations: u64,
+    // endpoints in the order of preference
+    pub sub_api_urls: Vec<String>,
     pub sub_validator_mnemonic_phrase: String,
+    pub storage_path: String,
 }
 
 impl Config {
-    pub fn load() -> Result<Self, &'static str> {
-        Ok(Config {
-            eth_api_url: parse_eth_api_url()?,
-            eth_validator_address: parse_eth_validator_address()?,
-            eth_validator_private_key: parse_eth_validator_private_key()?,
-            eth_contract_address: parse_eth_contract_address()?,
-            eth_relay_message_hash: parse_eth_relay_message_hash()?,
-            eth_approved_relay_message_hash: parse_eth_approved_relay_message_hash()?,
-            eth_withdraw_message_hash: parse_eth_withdraw_message_hash()?,
-            eth_gas_price: parse_eth_gas_price()?,
-            eth_gas: parse_eth_gas()?,
-            sub_api_url: parse_sub_api_url()?,
-            sub_validator_mnemonic_phrase: parse_sub_validator_mnemonic_phrase()?,
+    /// Loads the config file and overrides its values by the environment variables.
+    /// The file is optional unless its path is given explicitly.
+    /// All invalid values are reported, not only the first one.
+    pub fn load(path: Option<&str>) -> Result<Self, Vec<String>> {
+        let file = match path {
+            Some(path) => read_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => read_file(DEFAULT_CONFIG_PATH)?,
+            None => HashMap::new(),
+        };
+        Self::from_source(&Source {
+            file,
+            env: env::vars().collect(),
         })
     }
+
+    fn from_source(source: &Source) -> Result<Self, Vec<String>> {
+        let mut errors = Vec::new();
+        let config = Config {
+            eth_api_urls: check(&mut errors, parse_eth_api_urls(source)),
+            eth_chain_id: check(&mut errors, parse_eth_chain_id(source)),
+            eth_validator_address: check(&mut errors, parse_eth_validator_address(source)),
+            eth_validator_private_key: check(&mut errors, parse_eth_validator_private_key(source)),
+            eth_contract_address: check(&mut errors, parse_eth_contract_address(source)),
+            eth_gas_price: check(&mut errors, parse_eth_gas_price(source)),
+            eth_gas_price_strategy: check(&mut errors, parse_eth_gas_price_strategy(source)),
+            eth_min_gas_price: check(&mut errors, parse_eth_min_gas_price(source)),
+            eth_max_gas_price: check(&mut errors, parse_eth_max_gas_price(source)),
+            eth_fee_history_blocks: check(&mut errors, parse_eth_fee_history_blocks(source)),
+            eth_gas: check(&mut errors, parse_eth_gas(source)),
+            eth_gas_margin_percent: check(&mut errors, parse_eth_gas_margin_percent(source)),
+            eth_confirmations: check(&mut errors, parse_eth_confirmations(source)),
+            sub_api_urls: check(&mut errors, parse_sub_api_urls(source)),
+            sub_validator_mnemonic_phrase: check(
+                &mut errors,
+                parse_sub_validator_mnemonic_phrase(source),
+            ),
+            storage_path: check(&mut errors, parse_storage_path(source)),
+        };
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// copy of the config without the keys of the validator accounts
+    pub fn redacted(&self) -> Self {
+        Config {
+            eth_validator_private_key: REDACTED.to_string(),
+            sub_validator_mnemonic_phrase: REDACTED.to_string(),
+            ..self.clone()
+        }
+    }
 }
 
-fn parse_eth_api_url() -> Result<String, &'static str> {
-    env::var("ETH_API_URL").map_err(|_| "can not read ETH_API_URL")
+/// values of the config file keyed by the environment variables overriding them
+struct Source {
+    file: HashMap<String, String>,
+    env: HashMap<String, String>,
 }
 
-fn parse_eth_validator_address() -> Result<Address, &'static str> {
-    let address =
-        env::var("ETH_VALIDATOR_ADDRESS").map_err(|_| "can not read ETH_VALIDATOR_ADDRESS")?;
-    address[2..]
+impl Source {
+    fn get(&self, name: &str) -> Option<String> {
+        self.env.get(name).or_else(|| self.file.get(name)).cloned()
+    }
+}
+
+fn read_file(path: &str) -> Result<HashMap<String, String>, Vec<String>> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| vec![format!("can not read config file {}: {}", path, err)])?;
+    parse_file(&content)
+}
+
+// `key` of `[section]` becomes the value of the environment variable with the prefix of the section,
+// lists are joined with commas as in ETH_API_URL
+fn parse_file(content: &str) -> Result<HashMap<String, String>, Vec<String>> {
+    let file = content
+        .parse::<toml::Value>()
+        .map_err(|err| vec![format!("can not parse config file: {}", err)])?;
+    let sections = match file {
+        toml::Value::Table(sections) => sections,
+        _ => return Err(vec!["can not parse config file".to_string()]),
+    };
+
+    let mut values = HashMap::new();
+    let mut errors = Vec::new();
+    for (section, keys) in sections {
+        let prefix = match SECTIONS.iter().find(|(name, _)| *name == section) {
+            Some((_, prefix)) => prefix,
+            None => {
+                errors.push(format!("unknown section [{}] in config file", section));
+                continue;
+            }
+        };
+        let keys = match keys {
+            toml::Value::Table(keys) => keys,
+            _ => {
+                errors.push(format!("[{}] of config file is not a section", section));
+                continue;
+            }
+        };
+        for (key, value) in keys {
+            let name = format!("{}{}", prefix, key.to_uppercase());
+            if !VARIABLES.contains(&name.as_str()) {
+                errors.push(format!("unknown key {}.{} in config file", section, key));
+                continue;
+            }
+            match file_value(value) {
+                Some(value) => {
+                    values.insert(name, value);
+                }
+                None => errors.push(format!(
+                    "invalid value of {}.{} in config file, expected a string, an integer or a list of strings",
+                    section, key
+                )),
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(values)
+    } else {
+        Err(errors)
+    }
+}
+
+fn file_value(value: toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(value) => Some(value),
+        toml::Value::Integer(value) => Some(value.to_string()),
+        toml::Value::Array(values) => values
+            .into_iter()
+            .map(|value| value.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .map(|values| values.join(",")),
+        _ => None,
+    }
+}
+
+fn check<T: Default>(errors: &mut Vec<String>, result: Result<T, &'static str>) -> T {
+    result.unwrap_or_else(|err| {
+        errors.push(err.to_string());
+        T::default()
+    })
+}
+
+fn parse_eth_api_urls(source: &Source) -> Result<Vec<String>, &'static str> {
+    let urls = source
+        .get("ETH_API_URL")
+        .ok_or("can not read ETH_API_URL")?;
+    parse_urls(&urls).ok_or("can not parse ETH_API_URL, expected ws:// or wss:// urls")
+}
+
+fn parse_eth_chain_id(source: &Source) -> Result<u64, &'static str> {
+    source
+        .get("ETH_CHAIN_ID")
+        .ok_or("can not read ETH_CHAIN_ID")?
         .parse()
-        .map_err(|_| "can not parse validator address")
+        .map_err(|_| "can not parse ETH_CHAIN_ID")
+}
+
+fn parse_eth_validator_address(source: &Source) -> Result<Address, &'static str> {
+    let address = source
+        .get("ETH_VALIDATOR_ADDRESS")
+        .ok_or("can not read ETH_VALIDATOR_ADDRESS")?;
+    parse_hex(&address).map_err(|_| "can not parse validator address")
 }
 
-fn parse_eth_validator_private_key() -> Result<String, &'static str> {
-    let private_key = env::var("ETH_VALIDATOR_PRIVATE_KEY")
-        .map_err(|_| "can not read ETH_VALIDATOR_PRIVATE_KEY")?;
-    let private_key = private_key[2..].to_string();
+fn parse_eth_validator_private_key(source: &Source) -> Result<String, &'static str> {
+    let private_key = source
+        .get("ETH_VALIDATOR_PRIVATE_KEY")
+        .ok_or("can not read ETH_VALIDATOR_PRIVATE_KEY")?;
+    let private_key = without_hex_prefix(&private_key).to_string();
     try_convert_to_bip32_key_pair(&private_key)?;
 
     Ok(private_key)
 }
 
-fn parse_eth_contract_address() -> Result<Address, &'static str> {
-    let address =
-        env::var("ETH_CONTRACT_ADDRESS").map_err(|_| "can not read ETH_CONTRACT_ADDRESS")?;
-    address[2..]
+fn parse_eth_contract_address(source: &Source) -> Result<Address, &'static str> {
+    let address = source
+        .get("ETH_CONTRACT_ADDRESS")
+        .ok_or("can not read ETH_CONTRACT_ADDRESS")?;
+    parse_hex(&address).map_err(|_| "can not parse contract address")
+}
+
+fn parse_eth_gas_price(source: &Source) -> Result<u64, &'static str> {
+    source
+        .get("ETH_GAS_PRICE")
+        .unwrap_or_else(|| DEFAULT_GAS_PRICE.to_string())
         .parse()
-        .map_err(|_| "can not parse contract address")
+        .map_err(|_| "can not parse ETH_GAS_PRICE")
 }
 
-fn parse_eth_relay_message_hash() -> Result<H256, &'static str> {
-    let hash =
-        env::var("ETH_RELAY_MESSAGE_HASH").map_err(|_| "can not read ETH_RELAY_MESSAGE_HASH")?;
-    hash[2..]
+fn parse_eth_gas_price_strategy(source: &Source) -> Result<GasPriceStrategy, &'static str> {
+    match source.get("ETH_GAS_PRICE_STRATEGY") {
+        None => Ok(GasPriceStrategy::default()),
+        Some(strategy) => match strategy.as_str() {
+            "fixed" => Ok(GasPriceStrategy::Fixed),
+            "node" => Ok(GasPriceStrategy::Node),
+            "fee_history" => Ok(GasPriceStrategy::FeeHistory),
+            _ => Err("can not parse ETH_GAS_PRICE_STRATEGY, expected fixed, node or fee_history"),
+        },
+    }
+}
+
+fn parse_eth_gas(source: &Source) -> Result<u64, &'static str> {
+    source
+        .get("ETH_GAS")
+        .unwrap_or_else(|| DEFAULT_GAS.to_string())
         .parse()
-        .map_err(|_| "can not parse relay message hash")
+        .map_err(|_| "can not parse ETH_GAS")
 }
 
-fn parse_eth_approved_relay_message_hash() -> Result<H256, &'static str> {
-    let hash = env::var("ETH_APPROVED_RELAY_MESSAGE_HASH")
-        .map_err(|_| "can not read ETH_APPROVED_RELAY_MESSAGE_HASH")?;
-    hash[2..]
+fn parse_eth_confirmations(source: &Source) -> Result<u64, &'static str> {
+    source
+        .get("ETH_CONFIRMATIONS")
+        .unwrap_or_else(|| DEFAULT_ETH_CONFIRMATIONS.to_string())
         .parse()
-        .map_err(|_| "can not parse approved relay message hash")
+        .map_err(|_| "can not parse ETH_CONFIRMATIONS")
 }
 
-fn parse_eth_withdraw_message_hash() -> Result<H256, &'static str> {
-    let hash = env::var("ETH_WITHDRAW_MESSAGE_HASH")
-        .map_err(|_| "can not read ETH_WITHDRAW_MESSAGE_HASH")?;
-    hash[2..]
+fn parse_eth_min_gas_price(source: &Source) -> Result<u64, &'static str> {
+    source
+        .get("ETH_MIN_GAS_PRICE")
+        .unwrap_or_else(|| DEFAULT_MIN_GAS_PRICE.to_string())
         .parse()
-        .map_err(|_| "can not parse withdraw message hash")
+        .map_err(|_| "can not parse ETH_MIN_GAS_PRICE")
 }
 
-fn parse_eth_gas_price() -> Result<u64, &'static str> {
-    env::var("ETH_GAS_PRICE")
-        .or_else(|_| Ok(DEFAULT_GAS_PRICE.to_string()))
-        .map(|x| x.parse().expect("can not parse ETH_GAS_PRICE"))
+fn parse_eth_max_gas_price(source: &Source) -> Result<u64, &'static str> {
+    let max_gas_price: u64 = source
+        .get("ETH_MAX_GAS_PRICE")
+        .unwrap_or_else(|| DEFAULT_MAX_GAS_PRICE.to_string())
+        .parse()
+        .map_err(|_| "can not parse ETH_MAX_GAS_PRICE")?;
+    match parse_eth_min_gas_price(source) {
+        Ok(min_gas_price) if min_gas_price > max_gas_price => {
+            Err("ETH_MAX_GAS_PRICE is lower than ETH_MIN_GAS_PRICE")
+        }
+        _ => Ok(max_gas_price),
+    }
 }
 
-fn parse_eth_gas() -> Result<u64, &'static str> {
-    env::var("ETH_GAS")
-        .or_else(|_| Ok(DEFAULT_GAS.to_string()))
-        .map(|x| x.parse().expect("can not parse ETH_GAS"))
+fn parse_eth_fee_history_blocks(source: &Source) -> Result<u64, &'static str> {
+    source
+        .get("ETH_FEE_HISTORY_BLOCKS")
+        .unwrap_or_else(|| DEFAULT_FEE_HISTORY_BLOCKS.to_string())
+        .parse()
+        .map_err(|_| "can not parse ETH_FEE_HISTORY_BLOCKS")
+}
+
+fn parse_eth_gas_margin_percent(source: &Source) -> Result<u64, &'static str> {
+    source
+        .get("ETH_GAS_MARGIN_PERCENT")
+        .unwrap_or_else(|| DEFAULT_GAS_MARGIN_PERCENT.to_string())
+        .parse()
+        .map_err(|_| "can not parse ETH_GAS_MARGIN_PERCENT")
 }
 
-fn parse_sub_api_url() -> Result<String, &'static str> {
-    env::var("SUB_API_URL").map_err(|_| "can not read SUB_API_URL")
+fn parse_sub_api_urls(source: &Source) -> Result<Vec<String>, &'static str> {
+    let urls = source
+        .get("SUB_API_URL")
+        .ok_or("can not read SUB_API_URL")?;
+    parse_urls(&urls).ok_or("can not parse SUB_API_URL, expected ws:// or wss:// urls")
 }
 
-fn parse_sub_validator_mnemonic_phrase() -> Result<String, &'static str> {
-    let mnemonic_phrase = env::var("SUB_VALIDATOR_MNEMONIC_PHRASE")
-        .map_err(|_| "can not read SUB_VALIDATOR_MNEMONIC_PHRASE")?;
+fn parse_sub_validator_mnemonic_phrase(source: &Source) -> Result<String, &'static str> {
+    let mnemonic_phrase = source
+        .get("SUB_VALIDATOR_MNEMONIC_PHRASE")
+        .ok_or("can not read SUB_VALIDATOR_MNEMONIC_PHRASE")?;
     try_convert_to_sr25519_key_pair(&mnemonic_phrase)?;
 
     Ok(mnemonic_phrase)
 }
 
+fn parse_storage_path(source: &Source) -> Result<String, &'static str> {
+    Ok(source
+        .get("STORAGE_PATH")
+        .unwrap_or_else(|| DEFAULT_STORAGE_PATH.to_string()))
+}
+
+// comma separated websocket urls, at least one
+fn parse_urls(urls: &str) -> Option<Vec<String>> {
+    let urls = urls
+        .split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    let valid = urls
+        .iter()
+        .all(|url| url.starts_with("ws://") || url.starts_with("wss://"));
+    if urls.is_empty() || !valid {
+        return None;
+    }
+    Some(urls)
+}
+
+fn parse_hex<T: FromStr>(value: &str) -> Result<T, T::Err> {
+    without_hex_prefix(value).parse()
+}
+
+fn without_hex_prefix(value: &str) -> &str {
+    if value.starts_with("0x") {
+        &value[2..]
+    } else {
+        value
+    }
+}
+
 fn try_convert_to_sr25519_key_pair(mnemonic_phrase: &str) -> Result<(), &'static str> {
     sr25519::Pair::from_phrase(&mnemonic_phrase, None)
         .map_err(|_| "invalid SUB_VALIDATOR_MNEMONIC_PHRASE")?;
@@ -132,3 +411,129 @@ fn try_convert_to_bip32_key_pair(private_key: &str) -> Result<(), &'static str>
     Bip32ECKeyPair::from_raw_secret(&private_key).map_err(|_| "invalid validator private key")?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG_FILE: &str = r#"
+[ethereum]
+api_url = ["ws://localhost:9545", "wss://ethereum.example.com/ws"]
+chain_id = 42
+validator_address = "0xC6870AED119d01CE3f7A377775bD489839c51815"
+validator_private_key = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+contract_address = "0xE019339b8742a56A9e7193225f8b93aDa82114A8"
+gas_price_strategy = "fee_history"
+
+[substrate]
+api_url = "ws://localhost:9944"
+validator_mnemonic_phrase = "bottom drive obey lake curtain smoke basket hold race lonely fit walk"
+"#;
+
+    fn source(file: &str, env: &[(&str, &str)]) -> Source {
+        Source {
+            file: parse_file(file).unwrap(),
+            env: env
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn config_file_should_be_overridden_by_env() {
+        let config = Config::from_source(&source(
+            CONFIG_FILE,
+            &[
+                ("ETH_CHAIN_ID", "1"),
+                ("STORAGE_PATH", "/var/lib/validator"),
+            ],
+        ))
+        .unwrap();
+
+        assert_eq!(
+            config.eth_api_urls,
+            vec!["ws://localhost:9545", "wss://ethereum.example.com/ws"]
+        );
+        assert_eq!(config.eth_chain_id, 1);
+        assert_eq!(
+            config.eth_validator_private_key,
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+        );
+        assert_eq!(config.eth_gas_price_strategy, GasPriceStrategy::FeeHistory);
+        assert_eq!(config.eth_gas, DEFAULT_GAS);
+        assert_eq!(config.sub_api_urls, vec!["ws://localhost:9944"]);
+        assert_eq!(config.storage_path, "/var/lib/validator");
+    }
+
+    #[test]
+    fn example_config_file_should_be_valid() {
+        let example = include_str!("../validator.example.toml");
+        assert!(Config::from_source(&source(example, &[])).is_ok());
+    }
+
+    #[test]
+    fn all_errors_should_be_reported() {
+        let errors = Config::from_source(&source(
+            "",
+            &[
+                ("ETH_API_URL", "http://localhost:9545"),
+                ("ETH_VALIDATOR_ADDRESS", "0"),
+                ("ETH_VALIDATOR_PRIVATE_KEY", "0x"),
+                ("ETH_GAS", "lots"),
+                ("ETH_MIN_GAS_PRICE", "2000"),
+                ("ETH_MAX_GAS_PRICE", "1000"),
+            ],
+        ))
+        .unwrap_err();
+
+        assert_eq!(
+            errors,
+            vec![
+                "can not parse ETH_API_URL, expected ws:// or wss:// urls",
+                "can not read ETH_CHAIN_ID",
+                "can not parse validator address",
+                "invalid validator private key",
+                "can not read ETH_CONTRACT_ADDRESS",
+                "ETH_MAX_GAS_PRICE is lower than ETH_MIN_GAS_PRICE",
+                "can not parse ETH_GAS",
+                "can not read SUB_API_URL",
+                "can not read SUB_VALIDATOR_MNEMONIC_PHRASE",
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_keys_should_be_reported() {
+        let errors = parse_file(
+            r#"
+[ethereum]
+chainid = 42
+confirmations = true
+
+[bitcoin]
+api_url = "tcp://localhost:8333"
+"#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            errors,
+            vec![
+                "unknown section [bitcoin] in config file",
+                "unknown key ethereum.chainid in config file",
+                "invalid value of ethereum.confirmations in config file, expected a string, an integer or a list of strings",
+            ]
+        );
+    }
+
+    #[test]
+    fn redacted_config_should_not_contain_keys() {
+        let config = Config::from_source(&source(CONFIG_FILE, &[])).unwrap();
+        let redacted = format!("{:?}", config.redacted());
+
+        assert!(!redacted.contains(&config.eth_validator_private_key));
+        assert!(!redacted.contains(&config.sub_validator_mnemonic_phrase));
+        assert!(redacted.contains(REDACTED));
+    }
+}
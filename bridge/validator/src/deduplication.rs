@@ -0,0 +1,251 @@
+use ethsign_crypto::Keccak256;
+use futures::future::{self, Either};
+use parity_codec::{Decode, Encode};
+use rustc_hex::ToHex;
+use serde_json::Value;
+use web3::{
+    contract::{Contract, Options},
+    futures::Future,
+    types::{Bytes, CallRequest, H256, U256},
+};
+
+use node_runtime::types::{BridgeTransfer, ProposalId, Status, TransferMessage};
+use node_runtime::{AccountId, Hash};
+use primitives;
+use substrate_api_client::{hexstr_to_vec, Api};
+
+use crate::config;
+use crate::error::Error;
+use crate::ethereum_transactions::ERROR_SELECTOR;
+use crate::storage::{Action, ActionState, Storage};
+
+/// Logs and events are delivered again after re-subscription or backfill,
+/// the validator votes once per message and action. Dropped transactions are sent again.
+pub fn already_done(
+    storage: &Storage,
+    message_id: &primitives::H256,
+    action: Action,
+) -> Result<bool, Error> {
+    let done = storage
+        .action(message_id, action)?
+        .map_or(false, |record| record.state != ActionState::Dropped);
+    if done {
+        log::info!(
+            "[dedup] skipping {:?} of {:?}, it is done already",
+            action,
+            message_id
+        );
+    }
+    Ok(done)
+}
+
+/// the substrate proposal is finished, there is no need to vote for it
+pub fn substrate_proposal_is_closed(
+    sub_api: &Api,
+    message_id: &primitives::H256,
+    action: Action,
+) -> bool {
+    let transfer =
+        get_storage::<ProposalId>(sub_api, "TransferId", message_id.encode()).and_then(|id| {
+            get_storage::<BridgeTransfer<Hash>>(sub_api, "BridgeTransfers", id.encode())
+        });
+    let transfer = match transfer {
+        Some(transfer) => transfer,
+        // the first vote creates the proposal
+        None => return false,
+    };
+    let status = get_storage::<TransferMessage<AccountId, Hash>>(
+        sub_api,
+        "TransferMessages",
+        message_id.encode(),
+    )
+    .map(|message| message.status);
+
+    let closed = match action {
+        // approved transfer is reopened by the first confirmation
+        Action::SubConfirmTransfer => !transfer.open && status == Some(Status::Confirmed),
+        _ => !transfer.open,
+    };
+    if closed {
+        log::info!(
+            "[dedup] skipping {:?} of {:?}, the proposal is closed",
+            action,
+            message_id
+        );
+    }
+    closed
+}
+
+fn get_storage<V: Decode>(sub_api: &Api, storage_key_name: &str, key: Vec<u8>) -> Option<V> {
+    let value = sub_api
+        .get_storage("Bridge", storage_key_name, Some(key))
+        .ok()?;
+    V::decode(&mut &hexstr_to_vec(value).ok()?[..])
+}
+
+/// The contract counts votes of validators per operation, which is a hash of the call data
+/// and the validators generation. Resolves to false if this validator has voted already.
+/// Votes of a performed operation are cleared, so the call is simulated too:
+/// it reverts once the message is handled by the contract. Other failures of the call
+/// fail the handler, the message is handled again after the restart.
+pub fn ethereum_vote_is_needed<T>(
+    web3: &web3::Web3<T>,
+    abi: &ethabi::Contract,
+    config: &config::Config,
+    data: Vec<u8>,
+) -> impl Future<Item = bool, Error = Error>
+where
+    T: web3::Transport + Send + 'static,
+    T::Out: Send,
+{
+    let call = CallRequest {
+        from: Some(config.eth_validator_address),
+        to: config.eth_contract_address,
+        gas: Some(U256::from(config.eth_gas)),
+        gas_price: None,
+        value: None,
+        data: Some(Bytes::from(data.clone())),
+    };
+    let eth = web3.eth();
+    not_voted(web3, abi, config, data)
+        .from_err()
+        .and_then(move |not_voted| {
+            if !not_voted {
+                return Either::A(future::ok(false));
+            }
+            Either::B(eth.call(call, None).then(|result| match result {
+                Ok(_) => Ok(true),
+                // the node reports a reverted call as an rpc error
+                Err(web3::Error::Rpc(ref err)) if is_revert(&err.message, err.data.as_ref()) => {
+                    log::info!(
+                        "[dedup] skipping ethereum operation, the call reverts: {:?}",
+                        err
+                    );
+                    Ok(false)
+                }
+                Err(err) => Err(Error::from(err)),
+            }))
+        })
+}
+
+/// `withdrawTransfer` has no status check in the contract, a transfer withdrawn already
+/// would be voted for again. Resolves to false if its WithdrawMessage is handled by the
+/// ethereum handler, the vote is checked as usual otherwise.
+pub fn ethereum_withdrawal_is_needed<T>(
+    web3: &web3::Web3<T>,
+    abi: &ethabi::Contract,
+    config: &config::Config,
+    storage: &Storage,
+    message_id: primitives::H256,
+    data: Vec<u8>,
+) -> impl Future<Item = bool, Error = Error>
+where
+    T: web3::Transport + Send + 'static,
+    T::Out: Send,
+{
+    let withdrawn = match storage.is_withdrawn(&message_id) {
+        Ok(withdrawn) => withdrawn,
+        Err(err) => return Either::A(future::err(err)),
+    };
+    if withdrawn {
+        log::info!(
+            "[dedup] skipping withdrawal of {:?}, it is withdrawn already",
+            message_id
+        );
+        return Either::A(future::ok(false));
+    }
+    Either::B(ethereum_vote_is_needed(web3, abi, config, data))
+}
+
+// the reverted call is told apart from failures of the node by the message or the encoded reason
+fn is_revert(message: &str, data: Option<&Value>) -> bool {
+    let reason = format!("0x{}", ERROR_SELECTOR.to_hex::<String>());
+    message.to_lowercase().contains("revert")
+        || data
+            .and_then(Value::as_str)
+            .map_or(false, |data| data.to_lowercase().starts_with(&reason))
+}
+
+// false if this validator has voted for the operation of the current generation
+fn not_voted<T>(
+    web3: &web3::Web3<T>,
+    abi: &ethabi::Contract,
+    config: &config::Config,
+    data: Vec<u8>,
+) -> impl Future<Item = bool, Error = web3::contract::Error>
+where
+    T: web3::Transport + Send + 'static,
+    T::Out: Send,
+{
+    let contract = Contract::new(web3.eth(), config.eth_contract_address, abi.clone());
+    let validator = config.eth_validator_address;
+
+    let generation = contract.query("validatorsGeneration", (), None, Options::default(), None);
+    let index = contract.query(
+        "validatorsIndices",
+        (validator,),
+        None,
+        Options::default(),
+        None,
+    );
+    generation
+        .join(index)
+        .and_then(move |(generation, index): (U256, U256)| {
+            let operation = operation_hash(&data, generation);
+            contract
+                .query(
+                    "votesMaskByOperation",
+                    (operation,),
+                    None,
+                    Options::default(),
+                    None,
+                )
+                .map(move |mask: U256| {
+                    // index is 1-based, zero means the account is not a validator
+                    let voted = !index.is_zero() && mask.bit(index.low_u64() as usize - 1);
+                    if voted {
+                        log::info!(
+                            "[dedup] skipping ethereum operation {:?}, voted already",
+                            operation
+                        );
+                    }
+                    !voted
+                })
+        })
+}
+
+fn operation_hash(data: &[u8], generation: U256) -> H256 {
+    let mut packed = data.to_vec();
+    let mut generation_bytes = [0u8; 32];
+    generation.to_big_endian(&mut generation_bytes);
+    packed.extend_from_slice(&generation_bytes);
+    H256::from_slice(&packed.keccak256())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reverted_calls_should_be_recognized() {
+        assert!(is_revert("execution reverted", None));
+        assert!(is_revert(
+            "VM Exception while processing transaction: revert",
+            None
+        ));
+        let reason =
+            json!("0x08c379a0000000000000000000000000000000000000000000000000000000000000002");
+        assert!(is_revert("VM execution error.", Some(&reason)));
+    }
+
+    #[test]
+    fn node_failures_should_not_be_taken_for_reverts() {
+        assert!(!is_revert("header not found", None));
+        assert!(!is_revert(
+            "rate limit exceeded",
+            Some(&json!("try again later"))
+        ));
+        assert!(!is_revert("request timed out", Some(&json!({ "code": 1 }))));
+    }
+}
@@ -0,0 +1,81 @@
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The first endpoint accepting connections, endpoints are tried in the configured order.
+/// The first endpoint is returned if none of them is reachable, connecting to it reports the error.
+///
+/// Endpoints are probed only when a task starts. A task keeps its connections while it runs,
+/// failing over happens when the node fails the task and the supervisor restarts it:
+/// the restarted task selects the endpoints again and opens new connections for all its
+/// clients, the gas price oracle included.
+pub fn select(urls: &[String]) -> String {
+    match urls.iter().find(|url| is_reachable(url)) {
+        Some(url) => {
+            if url != &urls[0] {
+                log::warn!("[endpoints] {} is not reachable, using {}", urls[0], url);
+            }
+            url.clone()
+        }
+        None => urls[0].clone(),
+    }
+}
+
+fn is_reachable(url: &str) -> bool {
+    let addresses = socket_address(url).and_then(|address| address.to_socket_addrs().ok());
+    match addresses {
+        Some(mut addresses) => {
+            addresses.any(|address| TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).is_ok())
+        }
+        None => false,
+    }
+}
+
+// host and port of a websocket url
+fn socket_address(url: &str) -> Option<String> {
+    let (rest, default_port) = if url.starts_with("wss://") {
+        (&url["wss://".len()..], 443)
+    } else if url.starts_with("ws://") {
+        (&url["ws://".len()..], 80)
+    } else {
+        return None;
+    };
+    let authority = rest.split(|c| c == '/' || c == '?').next()?;
+    if authority.is_empty() {
+        return None;
+    }
+    // the colons of an IPv6 address are enclosed in brackets
+    let has_port = authority
+        .rfind(':')
+        .map_or(false, |index| !authority[index..].contains(']'));
+    if has_port {
+        Some(authority.to_string())
+    } else {
+        Some(format!("{}:{}", authority, default_port))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn socket_address_should_work() {
+        assert_eq!(
+            socket_address("ws://localhost:9944"),
+            Some("localhost:9944".to_string())
+        );
+        assert_eq!(
+            socket_address("wss://ethereum.example.com/ws?key=1"),
+            Some("ethereum.example.com:443".to_string())
+        );
+        assert_eq!(
+            socket_address("ws://[::1]:9545/"),
+            Some("[::1]:9545".to_string())
+        );
+        assert_eq!(socket_address("ws://[::1]"), Some("[::1]:80".to_string()));
+        assert_eq!(socket_address("http://localhost:9545"), None);
+        assert_eq!(socket_address("ws://"), None);
+    }
+}
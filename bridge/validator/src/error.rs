@@ -0,0 +1,72 @@
+use std::fmt;
+
+/// Failure of a validator task, the supervisor logs it and restarts the task
+#[derive(Debug)]
+pub enum Error {
+    Ethereum(web3::Error),
+    /// call of the bridge contract
+    Contract(web3::contract::Error),
+    Substrate(substrate_api_client::Error),
+    Abi(ethabi::Error),
+    Storage(&'static str),
+    /// the validator key can not be used for signing
+    Key(&'static str),
+    /// the substrate api is used before its genesis hash is read
+    ApiNotInitialized,
+    /// data of the node which the validator can not handle
+    InvalidData(String),
+    /// the ethereum node is on another chain than the configured one
+    ChainIdMismatch {
+        configured: u64,
+        node: u64,
+    },
+    Thread(std::io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Ethereum(err) => write!(f, "ethereum error: {}", err),
+            Error::Contract(err) => write!(f, "contract error: {}", err),
+            Error::Substrate(err) => write!(f, "substrate error: {}", err),
+            Error::Abi(err) => write!(f, "abi error: {}", err),
+            Error::Storage(err) => write!(f, "storage error: {}", err),
+            Error::Key(err) => write!(f, "key error: {}", err),
+            Error::ApiNotInitialized => write!(f, "substrate api is not initialized"),
+            Error::InvalidData(err) => write!(f, "invalid data: {}", err),
+            Error::ChainIdMismatch { configured, node } => write!(
+                f,
+                "chain id {} is configured by `chain_id` of `[ethereum]` or ETH_CHAIN_ID, \
+                 but the ethereum node is on chain {}",
+                configured, node
+            ),
+            Error::Thread(err) => write!(f, "can not start thread: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<web3::Error> for Error {
+    fn from(err: web3::Error) -> Self {
+        Error::Ethereum(err)
+    }
+}
+
+impl From<web3::contract::Error> for Error {
+    fn from(err: web3::contract::Error) -> Self {
+        Error::Contract(err)
+    }
+}
+
+impl From<substrate_api_client::Error> for Error {
+    fn from(err: substrate_api_client::Error) -> Self {
+        Error::Substrate(err)
+    }
+}
+
+impl From<ethabi::Error> for Error {
+    fn from(err: ethabi::Error) -> Self {
+        Error::Abi(err)
+    }
+}
@@ -1,254 +1,545 @@
-use ethabi::{self, ParamType};
+use ethabi::{self, Token};
 use futures::{
-    future::{lazy, poll_fn},
-    stream::Stream,
+    future::{self, poll_fn, Either},
+    stream::{self, Stream},
 };
 use log;
 use tokio_threadpool::blocking;
 use web3::{
     futures::Future,
-    types::{Bytes, Filter, FilterBuilder, Log},
+    types::{BlockHeader, BlockNumber, Filter, FilterBuilder, Log, H256, U256},
 };
 
 use primitives::sr25519;
 use substrate_api_client::Api;
 
+use std::collections::BTreeMap;
+use std::io;
 use std::sync::Arc;
 
 use crate::config;
+use crate::deduplication;
+use crate::endpoints;
+use crate::error::Error;
+use crate::ethereum_events::{
+    self, ApprovedRelayMessage, BridgeEvent, RelayMessage, RevertMessage, WithdrawMessage,
+};
 use crate::ethereum_transactions;
+use crate::gas::{self, SharedGasPriceOracle};
+use crate::nonce_manager::NonceManager;
+use crate::storage::{Action, ActionRecord, ActionState, Storage};
 use crate::substrate_transactions;
+use crate::transaction_tracker::{self, TransactionTracker};
+
+enum Incoming {
+    Log(Log),
+    Head(BlockHeader),
+}
+
+/// Logs are handled once they are buried under enough blocks, logs removed by a reorg
+/// before that are dropped and never acted on.
+#[derive(Default)]
+struct PendingLogs {
+    logs: BTreeMap<(u64, u64), Log>,
+}
+
+impl PendingLogs {
+    fn insert(&mut self, log: Log) {
+        let key = match (log.block_number, log.log_index) {
+            (Some(block_number), Some(log_index)) => (block_number.low_u64(), log_index.low_u64()),
+            _ => {
+                log::debug!("[ethereum] ignoring pending log: {:?}", log);
+                return;
+            }
+        };
+
+        if log.removed == Some(true) {
+            match self.logs.get(&key).map(|pending| pending.block_hash) {
+                Some(block_hash) if block_hash == log.block_hash => {
+                    self.logs.remove(&key);
+                    log::warn!("[ethereum] log is removed by reorg: {:?}", log);
+                }
+                // replaced by the log of the new chain already
+                Some(_) => (),
+                None => log::error!(
+                    "[ethereum] log is removed by reorg deeper than confirmation depth: {:?}",
+                    log
+                ),
+            }
+            return;
+        }
+
+        // a log of the new chain replaces the one of the same position in the old chain
+        self.logs.insert(key, log);
+    }
+
+    // logs of the blocks at least `confirmations` deep, grouped by block in the chain order
+    fn take_confirmed(&mut self, head: u64, confirmations: u64) -> Vec<(u64, Vec<Log>)> {
+        let confirmed_block = match head.checked_sub(confirmations) {
+            Some(number) => number,
+            None => return vec![],
+        };
+        let pending = self.logs.split_off(&(confirmed_block + 1, 0));
+        let confirmed = std::mem::replace(&mut self.logs, pending);
+
+        let mut blocks: Vec<(u64, Vec<Log>)> = vec![];
+        for ((block_number, _), log) in confirmed {
+            match blocks.last_mut() {
+                Some((number, logs)) if *number == block_number => logs.push(log),
+                _ => blocks.push((block_number, vec![log])),
+            }
+        }
+        blocks
+    }
+}
 
-const AMOUNT: u64 = 0;
+type HandlerFuture = Box<dyn Future<Item = (), Error = Error> + Send>;
 
-pub fn start(config: config::Config) {
-    let mut sub_api = Api::new(config.sub_api_url.clone());
-    sub_api.init();
-    let sub_api = Arc::new(sub_api);
+/// Handles confirmed logs of the bridge contract, returns when the subscription
+/// or one of the nodes fails. Logs are handled one by one, a block is stored as handled
+/// once the actions of all its logs are sent, so a restart handles the whole block again.
+pub fn start(
+    config: config::Config,
+    storage: Storage,
+    nonce_manager: NonceManager,
+    tracker: TransactionTracker,
+) -> Result<(), Error> {
+    let mut sub_api = Api::new(endpoints::select(&config.sub_api_urls));
+    sub_api.init()?;
 
-    let (_eloop, transport) = web3::transports::WebSocket::new(&config.eth_api_url).unwrap();
+    let (_eloop, transport) =
+        web3::transports::WebSocket::new(&endpoints::select(&config.eth_api_urls))?;
     let web3 = web3::Web3::new(transport);
+    // the oracle of a node uses the connection of this run, it is recreated on restart
+    let gas_price_oracle = gas::oracle(&config, web3.clone());
 
     let contact_abi = include_bytes!("../res/EthContract.abi");
-    let abi = ethabi::Contract::load(contact_abi.to_vec().as_slice()).expect("can read ABI");
+    let abi = ethabi::Contract::load(contact_abi.to_vec().as_slice())?;
 
-    let abi = Arc::new(abi);
-    let web3 = Arc::new(web3);
+    let topics = ethereum_events::topics(&abi);
+    log::info!("[ethereum] topics of handled events: {:?}", topics);
 
-    let fut = web3
+    // logs emitted while the validator was down, the subscription buffers new logs meanwhile
+    let backfill_filter = storage
+        .eth_block()?
+        .map(|number| build_filter(&config, &topics, Some(number + 1)));
+    let backfill_web3 = web3.clone();
+    let confirmations = config.eth_confirmations;
+    let heads = web3.eth_subscribe().subscribe_new_heads();
+    let logs = web3
         .eth_subscribe()
-        .subscribe_logs(build_filter(&config))
-        .then(move |sub| {
-            sub.unwrap().for_each(move |log| {
-                log::info!("[ethereum] got log: {:?}", log);
-                let received_relay_message = log
-                    .topics
-                    .iter()
-                    .any(|addr| addr == &config.eth_relay_message_hash);
-                let received_approved_relay_message = log
-                    .topics
-                    .iter()
-                    .any(|addr| addr == &config.eth_approved_relay_message_hash);
-                let withdraw_message = log
-                    .topics
-                    .iter()
-                    .any(|addr| addr == &config.eth_withdraw_message_hash);
-
-                match (
-                    received_relay_message,
-                    received_approved_relay_message,
-                    withdraw_message,
-                ) {
-                    (true, _, _) => handle_relay_message(log, web3.clone(), abi.clone(), &config),
-                    (_, true, _) => handle_approved_relay_message(log, sub_api.clone(), &config),
-                    (_, _, true) => handle_withdraw_message(log, sub_api.clone(), &config),
-                    (_, _, _) => {
-                        log::warn!("received unknown log: {:?}", log);
-                        Ok(())
-                    }
+        .subscribe_logs(build_filter(&config, &topics, None));
+
+    let handler = LogHandler {
+        web3,
+        abi: Arc::new(abi),
+        sub_api: Arc::new(sub_api),
+        config: Arc::new(config),
+        storage,
+        nonce_manager,
+        tracker,
+        gas_price_oracle,
+    };
+
+    let fut = logs
+        .join(heads)
+        .and_then(move |(sub, heads)| {
+            let backfill = match backfill_filter {
+                Some(filter) => {
+                    log::info!("[ethereum] backfilling logs: {:?}", filter);
+                    Either::A(backfill_web3.eth().logs(filter))
                 }
+                None => Either::B(future::ok(vec![])),
+            };
+            backfill.map(move |logs| {
+                stream::iter_ok(logs)
+                    .chain(sub)
+                    .map(Incoming::Log)
+                    .select(heads.map(Incoming::Head))
             })
         })
-        .map_err(|_| ());
+        .from_err::<Error>()
+        .and_then(move |incoming| {
+            let mut pending = PendingLogs::default();
+            incoming
+                .from_err::<Error>()
+                .for_each(move |incoming| match incoming {
+                    Incoming::Log(log) => {
+                        log::debug!("[ethereum] got pending log: {:?}", log);
+                        pending.insert(log);
+                        Either::B(future::ok(()))
+                    }
+                    Incoming::Head(head) => {
+                        let head = match head.number {
+                            Some(number) => number.low_u64(),
+                            None => return Either::B(future::ok(())),
+                        };
+                        let handler = handler.clone();
+                        let confirmed = pending.take_confirmed(head, confirmations);
+                        Either::A(stream::iter_ok::<_, Error>(confirmed).for_each(
+                            move |(block_number, logs)| handler.handle_block(block_number, logs),
+                        ))
+                    }
+                })
+        });
 
-    tokio::run(fut);
+    let mut runtime = tokio::runtime::Runtime::new().map_err(Error::Thread)?;
+    let result = runtime.block_on(fut);
+    let _ = runtime.shutdown_on_idle().wait();
+    result
 }
 
-fn build_filter(config: &config::Config) -> Filter {
-    FilterBuilder::default()
+fn build_filter(config: &config::Config, topics: &[H256], from_block: Option<u64>) -> Filter {
+    let builder = FilterBuilder::default()
         .address(vec![config.eth_contract_address])
-        .topics(
-            Some(vec![
-                config.eth_relay_message_hash,
-                config.eth_approved_relay_message_hash,
-                config.eth_withdraw_message_hash,
-            ]),
-            None,
-            None,
-            None,
-        )
-        .build()
+        .topics(Some(topics.to_vec()), None, None, None);
+    match from_block {
+        Some(number) => builder
+            .from_block(BlockNumber::Number(number))
+            .to_block(BlockNumber::Latest)
+            .build(),
+        None => builder.build(),
+    }
 }
 
-fn handle_relay_message<T>(
-    log: Log,
-    web3: Arc<web3::Web3<T>>,
+// everything the handling of a log needs, cloned into the futures of its actions
+#[derive(Clone)]
+struct LogHandler<T> {
+    web3: web3::Web3<T>,
     abi: Arc<ethabi::Contract>,
-    config: &config::Config,
-) -> Result<(), web3::error::Error>
+    sub_api: Arc<Api>,
+    config: Arc<config::Config>,
+    storage: Storage,
+    nonce_manager: NonceManager,
+    tracker: TransactionTracker,
+    gas_price_oracle: SharedGasPriceOracle,
+}
+
+impl<T> LogHandler<T>
 where
-    T: web3::Transport + Send + Sync + 'static,
+    T: web3::Transport + Clone + Send + Sync + 'static,
     T::Out: Send,
 {
-    let result = ethabi::decode(
-        &[
-            ParamType::FixedBytes(32),
-            ParamType::Address,
-            ParamType::FixedBytes(32),
-            ParamType::Uint(256),
-        ],
-        &log.data.0,
-    );
-    if let Ok(params) = result {
-        log::info!("[ethereum] got decoded log.data: {:?}", params);
-        if params.len() >= 4 {
-            let args = (
-                params[0].clone(),
-                params[1].clone(),
-                params[2].clone(),
-                params[3].clone(),
+    // actions already taken for the logs of a block handled again are skipped by deduplication
+    fn handle_block(&self, block_number: u64, logs: Vec<Log>) -> HandlerFuture {
+        let handler = self.clone();
+        let storage = self.storage.clone();
+        Box::new(
+            stream::iter_ok::<_, Error>(logs)
+                .for_each(move |log| handler.handle_log(log))
+                .and_then(move |_| storage.set_eth_block(block_number)),
+        )
+    }
+
+    // resolves once the action is sent, a failed action or a log of the handled event
+    // which can not be decoded fails the handler, its block is handled again after the restart
+    fn handle_log(&self, log: Log) -> HandlerFuture {
+        log::info!("[ethereum] got log: {:?}", log);
+
+        let handled = match ethereum_events::parse_log(&self.abi, &log) {
+            Ok(Some((event, params))) => {
+                log::info!("[ethereum] got decoded {}: {:?}", event.name(), params);
+                match event {
+                    BridgeEvent::RelayMessage => self.handle_relay_message(params),
+                    BridgeEvent::ApprovedRelayMessage => self.handle_approved_relay_message(params),
+                    BridgeEvent::RevertMessage => self.handle_revert_message(params),
+                    BridgeEvent::WithdrawMessage => self.handle_withdraw_message(params),
+                }
+            }
+            Ok(None) => {
+                log::warn!("received unknown log: {:?}", log);
+                Ok(done())
+            }
+            Err(err) => Err(Error::InvalidData(format!(
+                "can not decode log: {:?}, reason: {:?}",
+                log, err
+            ))),
+        };
+
+        Box::new(future::result(handled).flatten())
+    }
+
+    fn handle_relay_message(&self, params: Vec<Token>) -> Result<HandlerFuture, Error> {
+        let message = RelayMessage::decode(&params).ok_or_else(|| {
+            Error::InvalidData(format!("can not decode RelayMessage: {:?}", params))
+        })?;
+        let message_id = message.message_id;
+        let args = (
+            H256::from_slice(message_id.as_bytes()),
+            message.sender,
+            message.recipient,
+            message.amount,
+        );
+        if deduplication::already_done(&self.storage, &message_id, Action::EthApproveTransfer)? {
+            return Ok(done());
+        }
+
+        let data =
+            ethereum_transactions::build_transaction_data(&self.abi, "approveTransfer", args)?;
+        let vote_is_needed = deduplication::ethereum_vote_is_needed(
+            &self.web3,
+            &self.abi,
+            &self.config,
+            data.clone(),
+        );
+        let handler = self.clone();
+        Ok(Box::new(vote_is_needed.and_then(move |needed| {
+            if !needed {
+                return Either::B(future::ok(()));
+            }
+            log::info!(
+                "[ethereum] calling approveTransfer({:?}, {:?}, {:?}, {:?})",
+                args.0,
+                args.1,
+                args.2,
+                args.3
             );
+            Either::A(transaction_tracker::send_tracked(
+                handler.web3,
+                handler.gas_price_oracle,
+                handler.nonce_manager,
+                handler.tracker,
+                handler.storage,
+                &handler.config,
+                message_id,
+                Action::EthApproveTransfer,
+                data,
+            ))
+        })))
+    }
 
-            let eth_validator_private_key = config.eth_validator_private_key.clone();
-            let eth_contract_address = config.eth_contract_address;
-            let eth_gas_price = config.eth_gas_price;
-            let eth_gas = config.eth_gas;
-            let data = ethereum_transactions::build_transaction_data(&abi, "approveTransfer", args);
-            let fut = web3.eth().transaction_count(config.eth_validator_address, None)
-                .and_then(move |nonce| {
-                    let tx = ethereum_transactions::build(eth_validator_private_key, eth_contract_address, nonce, AMOUNT, eth_gas_price, eth_gas, data);
-                    log::debug!("raw approveTransfer: {:?}", tx);
-                    web3.eth().send_raw_transaction(Bytes::from(tx))
-                        .then(move |res| {
-                            match res {
-                                Ok(tx_res) => {
-                                    log::info!("[ethereum] called approveTransfer({:?}, {:?}, {:?}, {:?}), nonce: {:?}, result: {:?}",
-                                                params[0], params[1], params[2], params[3], nonce, tx_res);
-                                },
-                                Err(err) => {
-                                    log::warn!("[ethereum] can not send approveTransfer({:?}, {:?}, {:?}, {:?}), nonce: {:?}, reason: {:?}",
-                                                params[0], params[1], params[2], params[3], nonce, err);
-                                }
-                            }
-                            Ok(())
-                        })
+    fn handle_approved_relay_message(&self, params: Vec<Token>) -> Result<HandlerFuture, Error> {
+        let message = ApprovedRelayMessage::decode(&params).ok_or_else(|| {
+            Error::InvalidData(format!("can not decode ApprovedRelayMessage: {:?}", params))
+        })?;
+        // token balances of substrate are u64, a larger amount can not be minted
+        if message.amount > U256::from(u64::max_value()) {
+            return Err(Error::InvalidData(format!(
+                "amount of ApprovedRelayMessage does not fit into token balance: {:?}",
+                message
+            )));
+        }
+        let message_id = message.message_id;
+        let from = primitives::H160::from(message.sender.as_fixed_bytes());
+        let to = sr25519::Public::from_slice(message.recipient.as_bytes());
+        let amount = message.amount.low_u64();
 
-                })
-                .map_err(|e| log::warn!("can not get nonce: {:?}", e));
-            tokio::spawn(fut);
+        if deduplication::already_done(&self.storage, &message_id, Action::SubMint)? {
+            return Ok(done());
         }
+
+        let sub_validator_mnemonic_phrase = self.config.sub_validator_mnemonic_phrase.clone();
+        let sub_api = self.sub_api.clone();
+        let storage = self.storage.clone();
+        Ok(substrate_call(move || {
+            if deduplication::substrate_proposal_is_closed(&sub_api, &message_id, Action::SubMint) {
+                return Ok(());
+            }
+            substrate_transactions::mint(
+                sub_api.clone(),
+                sub_validator_mnemonic_phrase.clone(),
+                message_id,
+                from,
+                to.clone(),
+                amount,
+            )
+            .map_err(|err| {
+                log::warn!(
+                    "[substrate] can not call multi_signed_mint({:?}, {:?}, {:?}, {:?}), reason: {}",
+                    message_id,
+                    from,
+                    to,
+                    amount,
+                    err
+                );
+                err
+            })?;
+            log::info!(
+                "[substrate] called multi_signed_mint({:?}, {:?}, {:?}, {:?})",
+                message_id,
+                from,
+                to,
+                amount
+            );
+            storage.record_action(
+                &message_id,
+                ActionRecord {
+                    action: Action::SubMint,
+                    state: ActionState::Sent,
+                    tx_hash: None,
+                },
+            )
+        }))
     }
-    Ok(())
-}
 
-fn handle_approved_relay_message(
-    log: Log,
-    sub_api: Arc<Api>,
-    config: &config::Config,
-) -> Result<(), web3::error::Error> {
-    let result = ethabi::decode(
-        &[
-            ParamType::FixedBytes(32),
-            ParamType::Address,
-            ParamType::FixedBytes(32),
-            ParamType::Uint(256),
-        ],
-        &log.data.0,
-    );
-    if let Ok(params) = result {
-        log::info!("[ethereum] got decoded log.data: {:?}", params);
-        if params.len() >= 4 {
-            let message_id = params[0]
-                .clone()
-                .to_fixed_bytes()
-                .map(|x| primitives::H256::from_slice(&x))
-                .expect("can not parse message_id");
-            let from = params[1]
-                .clone()
-                .to_address()
-                .map(|x| primitives::H160::from(x.as_fixed_bytes()))
-                .expect("can not parse 'from' address");
-            let to = params[2]
-                .clone()
-                .to_fixed_bytes()
-                .map(|x| sr25519::Public::from_slice(&x))
-                .expect("can not parse 'to' address");
-            let amount = params[3]
-                .clone()
-                .to_uint()
-                .map(|x| x.low_u64())
-                .expect("can not parse amount");
-
-            let sub_validator_mnemonic_phrase = config.sub_validator_mnemonic_phrase.clone();
-            let sub_api = sub_api.clone();
-            tokio::spawn(lazy(move || {
-                poll_fn(move || {
-                    blocking(|| {
-                        substrate_transactions::mint(
-                            sub_api.clone(),
-                            sub_validator_mnemonic_phrase.clone(),
-                            message_id,
-                            from,
-                            to.clone(),
-                            amount,
-                        );
-                        log::info!(
-                            "[substrate] called multi_signed_mint({:?}, {:?}, {:?}, {:?})",
-                            message_id,
-                            from,
-                            to,
-                            amount
-                        );
-                    })
-                    .map_err(|_| panic!("the threadpool shut down"))
-                })
-            }));
+    fn handle_revert_message(&self, params: Vec<Token>) -> Result<HandlerFuture, Error> {
+        let message = RevertMessage::decode(&params).ok_or_else(|| {
+            Error::InvalidData(format!("can not decode RevertMessage: {:?}", params))
+        })?;
+        let message_id = message.message_id;
+
+        // the sender took the tokens back, the transfer can not be approved anymore
+        if deduplication::already_done(&self.storage, &message_id, Action::EthApproveTransfer)? {
+            return Ok(done());
         }
+        log::info!(
+            "[ethereum] transfer {:?} is reverted by {:?}, skipping its approval",
+            message_id,
+            message.sender
+        );
+        self.storage.record_action(
+            &message_id,
+            ActionRecord {
+                action: Action::EthApproveTransfer,
+                state: ActionState::Canceled,
+                tx_hash: None,
+            },
+        )?;
+        Ok(done())
     }
-    Ok(())
-}
 
-fn handle_withdraw_message(
-    log: Log,
-    sub_api: Arc<Api>,
-    config: &config::Config,
-) -> Result<(), web3::error::Error> {
-    let result = ethabi::decode(&[ParamType::FixedBytes(32)], &log.data.0);
-    if let Ok(params) = result {
-        log::info!("[ethereum] got decoded log.data: {:?}", params);
-        if params.len() >= 4 {
-            let message_id = params[0]
-                .clone()
-                .to_fixed_bytes()
-                .map(|x| primitives::H256::from_slice(&x))
-                .expect("can not parse message_id");
-
-            let sub_validator_mnemonic_phrase = config.sub_validator_mnemonic_phrase.clone();
-            let sub_api = sub_api.clone();
-            tokio::spawn(lazy(move || {
-                poll_fn(move || {
-                    blocking(|| {
-                        substrate_transactions::confirm_transfer(
-                            &sub_api,
-                            sub_validator_mnemonic_phrase.clone(),
-                            message_id,
-                        );
-                        log::info!("[substrate] called confirm_transfer({:?})", message_id);
-                    })
-                    .map_err(|_| panic!("the threadpool shut down"))
-                })
-            }));
+    fn handle_withdraw_message(&self, params: Vec<Token>) -> Result<HandlerFuture, Error> {
+        let message = WithdrawMessage::decode(&params).ok_or_else(|| {
+            Error::InvalidData(format!("can not decode WithdrawMessage: {:?}", params))
+        })?;
+        let message_id = message.message_id;
+        // the substrate handler does not vote for the withdrawal of this transfer anymore
+        self.storage.set_withdrawn(&message_id)?;
+
+        if deduplication::already_done(&self.storage, &message_id, Action::SubConfirmTransfer)? {
+            return Ok(done());
         }
+
+        // tokens are withdrawn on ethereum, the burn of the transfer is confirmed on substrate
+        let sub_validator_mnemonic_phrase = self.config.sub_validator_mnemonic_phrase.clone();
+        let sub_api = self.sub_api.clone();
+        let storage = self.storage.clone();
+        Ok(substrate_call(move || {
+            if deduplication::substrate_proposal_is_closed(
+                &sub_api,
+                &message_id,
+                Action::SubConfirmTransfer,
+            ) {
+                return Ok(());
+            }
+            substrate_transactions::confirm_transfer(
+                &sub_api,
+                sub_validator_mnemonic_phrase.clone(),
+                message_id,
+            )
+            .map_err(|err| {
+                log::warn!(
+                    "[substrate] can not call confirm_transfer({:?}), reason: {}",
+                    message_id,
+                    err
+                );
+                err
+            })?;
+            log::info!("[substrate] called confirm_transfer({:?})", message_id);
+            storage.record_action(
+                &message_id,
+                ActionRecord {
+                    action: Action::SubConfirmTransfer,
+                    state: ActionState::Sent,
+                    tx_hash: None,
+                },
+            )
+        }))
+    }
+}
+
+fn done() -> HandlerFuture {
+    Box::new(future::ok(()))
+}
+
+// substrate api calls block, they are run on a thread of the pool which may block
+fn substrate_call<F>(call: F) -> HandlerFuture
+where
+    F: Fn() -> Result<(), Error> + Send + 'static,
+{
+    Box::new(
+        poll_fn(move || {
+            blocking(|| call()).map_err(|_| {
+                Error::Thread(io::Error::new(
+                    io::ErrorKind::Other,
+                    "blocking call outside of the threadpool",
+                ))
+            })
+        })
+        .and_then(|result| result),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn log(block_number: u64, log_index: u64, block_hash: u64, removed: bool) -> Log {
+        serde_json::from_value(json!({
+            "address": "0xe019339b8742a56a9e7193225f8b93ada82114a8",
+            "topics": [],
+            "data": "0x",
+            "blockHash": format!("0x{:064x}", block_hash),
+            "blockNumber": format!("0x{:x}", block_number),
+            "logIndex": format!("0x{:x}", log_index),
+            "removed": removed,
+        }))
+        .unwrap()
+    }
+
+    fn block_hashes(blocks: Vec<(u64, Vec<Log>)>) -> Vec<(u64, Vec<H256>)> {
+        blocks
+            .into_iter()
+            .map(|(number, logs)| {
+                let hashes = logs.iter().filter_map(|log| log.block_hash).collect();
+                (number, hashes)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn log_removed_by_reorg_should_be_dropped() {
+        let mut pending = PendingLogs::default();
+        pending.insert(log(10, 0, 1, false));
+        pending.insert(log(10, 1, 1, false));
+        pending.insert(log(10, 0, 1, true));
+
+        let confirmed = block_hashes(pending.take_confirmed(100, 12));
+        assert_eq!(confirmed, vec![(10, vec![H256::from_low_u64_be(1)])]);
+        assert!(pending.take_confirmed(100, 12).is_empty());
+    }
+
+    #[test]
+    fn log_of_new_chain_should_replace_the_old_one() {
+        let mut pending = PendingLogs::default();
+        pending.insert(log(10, 0, 1, false));
+        pending.insert(log(10, 0, 2, false));
+        // the removal of the old log comes after the new one
+        pending.insert(log(10, 0, 1, true));
+
+        let confirmed = block_hashes(pending.take_confirmed(100, 12));
+        assert_eq!(confirmed, vec![(10, vec![H256::from_low_u64_be(2)])]);
+    }
+
+    #[test]
+    fn logs_should_be_taken_at_confirmation_depth() {
+        let mut pending = PendingLogs::default();
+        pending.insert(log(10, 1, 1, false));
+        pending.insert(log(10, 0, 1, false));
+        pending.insert(log(11, 0, 3, false));
+
+        assert!(pending.take_confirmed(5, 12).is_empty());
+        assert!(pending.take_confirmed(21, 12).is_empty());
+
+        let confirmed = pending.take_confirmed(22, 12);
+        assert_eq!(confirmed.len(), 1);
+        let (number, logs) = &confirmed[0];
+        assert_eq!(*number, 10);
+        let indices: Vec<_> = logs.iter().filter_map(|log| log.log_index).collect();
+        assert_eq!(indices, vec![U256::from(0), U256::from(1)]);
+
+        let confirmed = block_hashes(pending.take_confirmed(23, 12));
+        assert_eq!(confirmed, vec![(11, vec![H256::from_low_u64_be(3)])]);
     }
-    Ok(())
 }
@@ -0,0 +1,384 @@
+use ethabi::{self, RawLog, Token};
+use web3::types::{Address, Log, H256, U256};
+
+/// events of the bridge contract handled by the validator
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BridgeEvent {
+    RelayMessage,
+    ApprovedRelayMessage,
+    RevertMessage,
+    WithdrawMessage,
+}
+
+const HANDLED_EVENTS: [BridgeEvent; 4] = [
+    BridgeEvent::RelayMessage,
+    BridgeEvent::ApprovedRelayMessage,
+    BridgeEvent::RevertMessage,
+    BridgeEvent::WithdrawMessage,
+];
+
+impl BridgeEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            BridgeEvent::RelayMessage => "RelayMessage",
+            BridgeEvent::ApprovedRelayMessage => "ApprovedRelayMessage",
+            BridgeEvent::RevertMessage => "RevertMessage",
+            BridgeEvent::WithdrawMessage => "WithdrawMessage",
+        }
+    }
+}
+
+/// Topics of the handled events derived from their signatures in the ABI,
+/// logs of other events are not requested from the node.
+pub fn topics(abi: &ethabi::Contract) -> Vec<H256> {
+    HANDLED_EVENTS
+        .iter()
+        .map(|event| topic(abi, *event))
+        .collect()
+}
+
+/// topic of the handled event, the hash of its signature
+pub fn topic(abi: &ethabi::Contract, event: BridgeEvent) -> H256 {
+    event_abi(abi, event).signature()
+}
+
+/// Decodes the log of a handled event into its parameters in the order of the ABI,
+/// None if the log is emitted by another event.
+pub fn parse_log(
+    abi: &ethabi::Contract,
+    log: &Log,
+) -> Result<Option<(BridgeEvent, Vec<Token>)>, ethabi::Error> {
+    let topic = match log.topics.first() {
+        Some(topic) => topic,
+        None => return Ok(None),
+    };
+    let event = HANDLED_EVENTS
+        .iter()
+        .map(|event| (*event, event_abi(abi, *event)))
+        .find(|(_, event_abi)| &event_abi.signature() == topic);
+    let (event, event_abi) = match event {
+        Some(event) => event,
+        None => return Ok(None),
+    };
+
+    let decoded = event_abi.parse_log(RawLog {
+        topics: log.topics.clone(),
+        data: log.data.0.clone(),
+    })?;
+    let params = decoded
+        .params
+        .into_iter()
+        .map(|param| param.value)
+        .collect();
+    Ok(Some((event, params)))
+}
+
+/// `RelayMessage(bytes32 messageID, address sender, bytes32 recipient, uint256 amount)`,
+/// tokens are locked on ethereum for a substrate recipient
+#[derive(Debug, PartialEq, Eq)]
+pub struct RelayMessage {
+    pub message_id: primitives::H256,
+    pub sender: Address,
+    pub recipient: H256,
+    pub amount: U256,
+}
+
+impl RelayMessage {
+    /// None if the parameters are not the ones of the event
+    pub fn decode(params: &[Token]) -> Option<Self> {
+        let (message_id, sender, recipient, amount) = decode_transfer(params)?;
+        Some(RelayMessage {
+            message_id,
+            sender,
+            recipient,
+            amount,
+        })
+    }
+}
+
+/// `ApprovedRelayMessage(bytes32 messageID, address sender, bytes32 recipient, uint256 amount)`,
+/// the transfer is approved by the validators, the tokens are minted on substrate
+#[derive(Debug, PartialEq, Eq)]
+pub struct ApprovedRelayMessage {
+    pub message_id: primitives::H256,
+    pub sender: Address,
+    pub recipient: H256,
+    pub amount: U256,
+}
+
+impl ApprovedRelayMessage {
+    /// None if the parameters are not the ones of the event
+    pub fn decode(params: &[Token]) -> Option<Self> {
+        let (message_id, sender, recipient, amount) = decode_transfer(params)?;
+        Some(ApprovedRelayMessage {
+            message_id,
+            sender,
+            recipient,
+            amount,
+        })
+    }
+}
+
+/// `RevertMessage(bytes32 messageID, address sender, uint256 amount)`,
+/// the sender took back the tokens of a transfer which is not approved
+#[derive(Debug, PartialEq, Eq)]
+pub struct RevertMessage {
+    pub message_id: primitives::H256,
+    pub sender: Address,
+    pub amount: U256,
+}
+
+impl RevertMessage {
+    /// None if the parameters are not the ones of the event
+    pub fn decode(params: &[Token]) -> Option<Self> {
+        match params {
+            [Token::FixedBytes(message_id), Token::Address(sender), Token::Uint(amount)]
+                if message_id.len() == 32 =>
+            {
+                Some(RevertMessage {
+                    message_id: primitives::H256::from_slice(message_id),
+                    sender: *sender,
+                    amount: *amount,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// `WithdrawMessage(bytes32 MessageID)`, the tokens of a substrate transfer are withdrawn on ethereum
+#[derive(Debug, PartialEq, Eq)]
+pub struct WithdrawMessage {
+    pub message_id: primitives::H256,
+}
+
+impl WithdrawMessage {
+    /// None if the parameters are not the ones of the event
+    pub fn decode(params: &[Token]) -> Option<Self> {
+        match params {
+            [Token::FixedBytes(message_id)] if message_id.len() == 32 => Some(WithdrawMessage {
+                message_id: primitives::H256::from_slice(message_id),
+            }),
+            _ => None,
+        }
+    }
+}
+
+// parameters of the events of an ethereum to substrate transfer
+fn decode_transfer(params: &[Token]) -> Option<(primitives::H256, Address, H256, U256)> {
+    match params {
+        [Token::FixedBytes(message_id), Token::Address(sender), Token::FixedBytes(recipient), Token::Uint(amount)]
+            if message_id.len() == 32 && recipient.len() == 32 =>
+        {
+            Some((
+                primitives::H256::from_slice(message_id),
+                *sender,
+                H256::from_slice(recipient),
+                *amount,
+            ))
+        }
+        _ => None,
+    }
+}
+
+// the ABI is a part of the validator, all handled events are in it
+fn event_abi(abi: &ethabi::Contract, event: BridgeEvent) -> &ethabi::Event {
+    abi.event(event.name())
+        .expect("can not find bridge event in ABI")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn abi() -> ethabi::Contract {
+        ethabi::Contract::load(include_bytes!("../res/EthContract.abi").to_vec().as_slice())
+            .expect("can read ABI")
+    }
+
+    #[test]
+    fn topics_should_be_derived_from_abi() {
+        let topics = topics(&abi());
+        let expected: Vec<H256> = vec![
+            "fb65d1544ea97e32c62baf55f738f7bb44671998c927415ef03e52d2477e292f"
+                .parse()
+                .unwrap(),
+            "09e319e20ad99cf447dda7fc4281e91e521dbc630571067ecc0cf4c5eaa3d2f4"
+                .parse()
+                .unwrap(),
+            "2d83618e570f4b4e76854856b7bb06ee455ae36f3c6cc65984ab095389aad5d2"
+                .parse()
+                .unwrap(),
+            "2bc0cab4c5d11eba456a15f3d76bee2294a690fa965d2daf98d18afee3719fbf"
+                .parse()
+                .unwrap(),
+        ];
+        assert_eq!(topics, expected);
+    }
+
+    #[test]
+    fn parse_log_should_work() {
+        let log: Log = serde_json::from_value(json!({
+            "address": "0xe019339b8742a56a9e7193225f8b93ada82114a8",
+            "topics": ["0x2d83618e570f4b4e76854856b7bb06ee455ae36f3c6cc65984ab095389aad5d2"],
+            "data": "0x5d1f5c9e2ac14c1b4e1a2a6c0a5f8e2c3a0b1d4e5f60718293a4b5c6d7e8f901\
+                     000000000000000000000000c6870aed119d01ce3f7a377775bd489839c51815\
+                     0000000000000000000000000000000000000000000000000de0b6b3a7640000",
+            "blockNumber": "0x10",
+            "logIndex": "0x0",
+        }))
+        .unwrap();
+
+        let (event, params) = parse_log(&abi(), &log).unwrap().unwrap();
+        assert_eq!(event, BridgeEvent::RevertMessage);
+        assert_eq!(
+            RevertMessage::decode(&params),
+            Some(RevertMessage {
+                message_id: "5d1f5c9e2ac14c1b4e1a2a6c0a5f8e2c3a0b1d4e5f60718293a4b5c6d7e8f901"
+                    .parse()
+                    .unwrap(),
+                sender: "c6870aed119d01ce3f7a377775bd489839c51815".parse().unwrap(),
+                amount: U256::from(1_000_000_000_000_000_000u64),
+            })
+        );
+        assert_eq!(
+            params,
+            vec![
+                Token::FixedBytes(
+                    "5d1f5c9e2ac14c1b4e1a2a6c0a5f8e2c3a0b1d4e5f60718293a4b5c6d7e8f901"
+                        .parse::<H256>()
+                        .unwrap()
+                        .as_bytes()
+                        .to_vec()
+                ),
+                Token::Address(
+                    "c6870aed119d01ce3f7a377775bd489839c51815"
+                        .parse::<Address>()
+                        .unwrap()
+                ),
+                Token::Uint(U256::from(1_000_000_000_000_000_000u64)),
+            ]
+        );
+    }
+
+    #[test]
+    fn logs_of_other_events_should_be_ignored() {
+        let log: Log = serde_json::from_value(json!({
+            "address": "0xe019339b8742a56a9e7193225f8b93ada82114a8",
+            "topics": ["0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"],
+            "data": "0x",
+        }))
+        .unwrap();
+
+        assert_eq!(parse_log(&abi(), &log).unwrap(), None);
+    }
+
+    fn fixture(json: &str) -> Log {
+        serde_json::from_str(json).expect("can not parse log fixture")
+    }
+
+    #[test]
+    fn withdraw_message_should_be_decoded() {
+        let log = fixture(include_str!("../res/fixtures/withdraw_message.json"));
+
+        let (event, params) = parse_log(&abi(), &log).unwrap().unwrap();
+        assert_eq!(event, BridgeEvent::WithdrawMessage);
+        assert_eq!(
+            WithdrawMessage::decode(&params),
+            Some(WithdrawMessage {
+                message_id: "8fc2bc2a3b7f0d4e3c3f6b29f6d2c1b3f5c7c1e5a9d0a4b8c2e6f1a3b5d7e9f1"
+                    .parse()
+                    .unwrap(),
+            })
+        );
+    }
+
+    #[test]
+    fn withdraw_message_of_stale_abi_should_be_ignored() {
+        // WithdrawMessage(bytes32,bytes32,address,uint256) is not emitted by the contract
+        let log = fixture(include_str!(
+            "../res/fixtures/withdraw_message_stale_abi.json"
+        ));
+
+        assert_eq!(parse_log(&abi(), &log).unwrap(), None);
+    }
+
+    #[test]
+    fn truncated_withdraw_message_should_fail() {
+        let log = fixture(include_str!(
+            "../res/fixtures/withdraw_message_truncated.json"
+        ));
+
+        assert!(parse_log(&abi(), &log).is_err());
+    }
+
+    fn transfer_params() -> Vec<Token> {
+        vec![
+            Token::FixedBytes(vec![1u8; 32]),
+            Token::Address(Address::repeat_byte(2)),
+            Token::FixedBytes(vec![3u8; 32]),
+            Token::Uint(U256::from(1_000)),
+        ]
+    }
+
+    #[test]
+    fn transfer_messages_should_be_decoded() {
+        let params = transfer_params();
+        assert_eq!(
+            RelayMessage::decode(&params),
+            Some(RelayMessage {
+                message_id: primitives::H256::repeat_byte(1),
+                sender: Address::repeat_byte(2),
+                recipient: H256::repeat_byte(3),
+                amount: U256::from(1_000),
+            })
+        );
+        assert_eq!(
+            ApprovedRelayMessage::decode(&params),
+            Some(ApprovedRelayMessage {
+                message_id: primitives::H256::repeat_byte(1),
+                sender: Address::repeat_byte(2),
+                recipient: H256::repeat_byte(3),
+                amount: U256::from(1_000),
+            })
+        );
+    }
+
+    #[test]
+    fn transfer_messages_with_other_params_should_not_be_decoded() {
+        // missing amount
+        let params = transfer_params();
+        assert_eq!(RelayMessage::decode(&params[..3]), None);
+        // short recipient
+        let mut params = transfer_params();
+        params[2] = Token::FixedBytes(vec![3u8; 20]);
+        assert_eq!(ApprovedRelayMessage::decode(&params), None);
+        // the parameters of RevertMessage
+        let params = vec![
+            Token::FixedBytes(vec![1u8; 32]),
+            Token::Address(Address::repeat_byte(2)),
+            Token::Uint(U256::from(1_000)),
+        ];
+        assert_eq!(RelayMessage::decode(&params), None);
+        assert!(RevertMessage::decode(&params).is_some());
+        assert_eq!(RevertMessage::decode(&transfer_params()), None);
+    }
+
+    #[test]
+    fn withdraw_message_with_other_params_should_not_be_decoded() {
+        let message_id = vec![1u8; 32];
+        assert_eq!(WithdrawMessage::decode(&[]), None);
+        assert_eq!(
+            WithdrawMessage::decode(&[Token::FixedBytes(vec![1u8; 16])]),
+            None
+        );
+        assert_eq!(
+            WithdrawMessage::decode(&[
+                Token::FixedBytes(message_id.clone()),
+                Token::FixedBytes(message_id),
+            ]),
+            None
+        );
+    }
+}
@@ -1,22 +1,32 @@
 use raw_transaction_builder::{Bip32ECKeyPair, RawTransaction};
 use rustc_hex::FromHex;
-use web3::{self, contract::tokens::Tokenize, types::U256};
+use serde_json::Value;
+use web3::{self, contract::tokens::Tokenize, futures::Future, types::U256};
 
-const CHAIN_ID: u8 = 42;
+use crate::error::Error;
 
-pub fn build_transaction_data<P>(abi: &ethabi::Contract, function_name: &str, params: P) -> Vec<u8>
+/// selector of Error(string), the revert reason is encoded as a call of it
+pub const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Call data of the contract function, fails if the function is not in the ABI
+/// or the parameters do not match it, empty data would call the fallback function.
+pub fn build_transaction_data<P>(
+    abi: &ethabi::Contract,
+    function_name: &str,
+    params: P,
+) -> Result<Vec<u8>, Error>
 where
     P: Tokenize,
 {
     abi.function(function_name)
         .and_then(|function| function.encode_input(&params.into_tokens()))
-        .unwrap_or_else(|error| {
+        .map_err(|error| {
             log::warn!(
                 "can not build transaction data for {:?}: {:?}",
                 function_name,
                 error
             );
-            vec![]
+            Error::from(error)
         })
 }
 
@@ -28,7 +38,8 @@ pub fn build(
     gas_price: u64,
     gas: u64,
     data: Vec<u8>,
-) -> Vec<u8> {
+    chain_id: u64,
+) -> Result<Vec<u8>, Error> {
     let tx = RawTransaction {
         nonce,
         to: Some(to),
@@ -38,11 +49,69 @@ pub fn build(
         data,
     };
 
-    let bip32ec_keypair = Bip32ECKeyPair::from_raw_secret(
-        &private_key
-            .from_hex::<Vec<_>>()
-            .expect("can not parse private key"),
-    )
-    .expect("invalid private key");
-    tx.sign(&bip32ec_keypair, CHAIN_ID)
+    let private_key = private_key
+        .from_hex::<Vec<_>>()
+        .map_err(|_| Error::Key("can not parse private key"))?;
+    let bip32ec_keypair = Bip32ECKeyPair::from_raw_secret(&private_key)
+        .map_err(|_| Error::Key("invalid private key"))?;
+    Ok(tx.sign(&bip32ec_keypair, chain_id))
+}
+
+/// Chain id of the node, `net_version` is used by the nodes that do not support `eth_chainId`
+pub fn chain_id<T>(web3: &web3::Web3<T>) -> Result<u64, web3::Error>
+where
+    T: web3::Transport,
+{
+    let chain_id = web3
+        .transport()
+        .execute("eth_chainId", vec![])
+        .wait()
+        .and_then(|chain_id| match chain_id {
+            Value::String(ref hex) => u64::from_str_radix(hex.trim_start_matches("0x"), 16)
+                .map_err(|_| web3::Error::InvalidResponse(format!("invalid chain id: {}", hex))),
+            _ => Err(web3::Error::InvalidResponse(format!(
+                "invalid chain id: {:?}",
+                chain_id
+            ))),
+        });
+    match chain_id {
+        Ok(chain_id) => Ok(chain_id),
+        Err(err) => {
+            log::warn!(
+                "[ethereum] can not get eth_chainId, falling back to net_version: {:?}",
+                err
+            );
+            let version = web3.net().version().wait()?;
+            version.parse().map_err(|_| {
+                web3::Error::InvalidResponse(format!("invalid net version: {}", version))
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use web3::types::H256;
+
+    fn abi() -> ethabi::Contract {
+        ethabi::Contract::load(include_bytes!("../res/EthContract.abi").to_vec().as_slice())
+            .expect("can read ABI")
+    }
+
+    #[test]
+    fn transaction_data_should_be_built() {
+        let data =
+            build_transaction_data(&abi(), "confirmTransfer", (H256::repeat_byte(1),)).unwrap();
+        assert_eq!(data.len(), 4 + 32);
+        assert_eq!(&data[4..], H256::repeat_byte(1).as_bytes());
+    }
+
+    #[test]
+    fn transaction_data_of_invalid_call_should_fail() {
+        assert!(build_transaction_data(&abi(), "unknownFunction", (H256::zero(),)).is_err());
+        // confirmTransfer(bytes32) takes a single parameter
+        let params = (H256::zero(), U256::from(1));
+        assert!(build_transaction_data(&abi(), "confirmTransfer", params).is_err());
+    }
 }
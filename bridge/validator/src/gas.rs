@@ -0,0 +1,244 @@
+use futures::{future, Future};
+use serde_json::Value;
+use web3::{
+    helpers::serialize,
+    types::{Bytes, CallRequest, U256},
+    Transport,
+};
+
+use std::sync::Arc;
+
+use crate::config::{self, GasPriceStrategy};
+
+/// Source of the gas price of the validator transactions
+pub trait GasPriceOracle: Send + Sync {
+    fn gas_price(&self) -> Box<dyn Future<Item = U256, Error = web3::Error> + Send>;
+}
+
+pub type SharedGasPriceOracle = Arc<dyn GasPriceOracle>;
+
+/// the same price for every transaction
+pub struct FixedGasPrice(pub U256);
+
+impl GasPriceOracle for FixedGasPrice {
+    fn gas_price(&self) -> Box<dyn Future<Item = U256, Error = web3::Error> + Send> {
+        Box::new(future::ok(self.0))
+    }
+}
+
+/// `eth_gasPrice` of the node
+pub struct NodeGasPrice<T> {
+    web3: web3::Web3<T>,
+}
+
+impl<T> NodeGasPrice<T> {
+    pub fn new(web3: web3::Web3<T>) -> Self {
+        NodeGasPrice { web3 }
+    }
+}
+
+impl<T> GasPriceOracle for NodeGasPrice<T>
+where
+    T: Transport + Send + Sync,
+    T::Out: Send + 'static,
+{
+    fn gas_price(&self) -> Box<dyn Future<Item = U256, Error = web3::Error> + Send> {
+        Box::new(self.web3.eth().gas_price())
+    }
+}
+
+/// Base fee of the next block plus the median priority fee paid in recent blocks (`eth_feeHistory`)
+pub struct FeeHistoryGasPrice<T> {
+    web3: web3::Web3<T>,
+    blocks: u64,
+}
+
+impl<T> FeeHistoryGasPrice<T> {
+    pub fn new(web3: web3::Web3<T>, blocks: u64) -> Self {
+        FeeHistoryGasPrice { web3, blocks }
+    }
+}
+
+impl<T> GasPriceOracle for FeeHistoryGasPrice<T>
+where
+    T: Transport + Send + Sync,
+    T::Out: Send + 'static,
+{
+    fn gas_price(&self) -> Box<dyn Future<Item = U256, Error = web3::Error> + Send> {
+        let params = vec![
+            serialize(&U256::from(self.blocks)),
+            Value::String("latest".to_string()),
+            serialize(&vec![50]),
+        ];
+        let fee_history = self
+            .web3
+            .transport()
+            .execute("eth_feeHistory", params)
+            .and_then(|history| {
+                parse_fee_history(&history).ok_or_else(|| {
+                    web3::Error::InvalidResponse(format!("invalid fee history: {:?}", history))
+                })
+            });
+        Box::new(fee_history)
+    }
+}
+
+// the last base fee is the one of the next block
+fn parse_fee_history(history: &Value) -> Option<U256> {
+    let base_fee = parse_quantity(history["baseFeePerGas"].as_array()?.last()?)?;
+    let mut rewards = history["reward"]
+        .as_array()?
+        .iter()
+        .filter_map(|reward| parse_quantity(reward.get(0)?))
+        .collect::<Vec<_>>();
+    rewards.sort();
+    let priority_fee = rewards.get(rewards.len() / 2).cloned().unwrap_or_default();
+    Some(base_fee.saturating_add(priority_fee))
+}
+
+fn parse_quantity(value: &Value) -> Option<U256> {
+    let value = value.as_str()?.trim_start_matches("0x");
+    U256::from_str_radix(value, 16).ok()
+}
+
+/// keeps the price of another oracle within the configured bounds
+pub struct CappedGasPrice {
+    oracle: Box<dyn GasPriceOracle>,
+    min: U256,
+    max: U256,
+}
+
+impl CappedGasPrice {
+    pub fn new(oracle: Box<dyn GasPriceOracle>, min: U256, max: U256) -> Self {
+        CappedGasPrice { oracle, min, max }
+    }
+}
+
+impl GasPriceOracle for CappedGasPrice {
+    fn gas_price(&self) -> Box<dyn Future<Item = U256, Error = web3::Error> + Send> {
+        let (min, max) = (self.min, self.max);
+        Box::new(
+            self.oracle
+                .gas_price()
+                .map(move |price| price.max(min).min(max)),
+        )
+    }
+}
+
+pub fn oracle<T>(config: &config::Config, web3: web3::Web3<T>) -> SharedGasPriceOracle
+where
+    T: Transport + Send + Sync + 'static,
+    T::Out: Send + 'static,
+{
+    let oracle: Box<dyn GasPriceOracle> = match config.eth_gas_price_strategy {
+        GasPriceStrategy::Fixed => Box::new(FixedGasPrice(U256::from(config.eth_gas_price))),
+        GasPriceStrategy::Node => Box::new(NodeGasPrice::new(web3)),
+        GasPriceStrategy::FeeHistory => {
+            Box::new(FeeHistoryGasPrice::new(web3, config.eth_fee_history_blocks))
+        }
+    };
+    Arc::new(CappedGasPrice::new(
+        oracle,
+        U256::from(config.eth_min_gas_price),
+        U256::from(config.eth_max_gas_price),
+    ))
+}
+
+/// Gas limit and gas price of a call of the bridge contract.
+/// The call is estimated with a safety margin, it is not sent if the estimation fails
+/// as the transaction would fail too.
+pub fn fees<T>(
+    web3: &web3::Web3<T>,
+    oracle: &dyn GasPriceOracle,
+    config: &config::Config,
+    data: Vec<u8>,
+) -> impl Future<Item = (u64, u64), Error = web3::Error>
+where
+    T: Transport,
+{
+    let call = CallRequest {
+        from: Some(config.eth_validator_address),
+        to: config.eth_contract_address,
+        gas: Some(U256::from(config.eth_gas)),
+        gas_price: None,
+        value: None,
+        data: Some(Bytes::from(data)),
+    };
+    let margin = config.eth_gas_margin_percent;
+    let max_gas = config.eth_gas;
+    let gas = web3.eth().estimate_gas(call, None).map(move |estimated| {
+        let gas = saturating_u64(estimated).saturating_mul(100 + margin) / 100;
+        if gas > max_gas {
+            log::warn!(
+                "[ethereum] estimated gas {} exceeds ETH_GAS {}",
+                gas,
+                max_gas
+            );
+        }
+        gas.min(max_gas)
+    });
+    gas.join(oracle.gas_price())
+        .map(|(gas, gas_price)| (gas, saturating_u64(gas_price)))
+}
+
+/// values reported by a node are capped, truncating them would turn a huge value into a small one
+pub fn saturating_u64(value: U256) -> u64 {
+    if value > U256::from(u64::max_value()) {
+        u64::max_value()
+    } else {
+        value.low_u64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn capped_gas_price_should_work() {
+        let capped = |price: u64| {
+            CappedGasPrice::new(
+                Box::new(FixedGasPrice(U256::from(price))),
+                U256::from(1_000_000_000u64),
+                U256::from(100_000_000_000u64),
+            )
+            .gas_price()
+            .wait()
+            .unwrap()
+        };
+        assert_eq!(capped(1), U256::from(1_000_000_000u64));
+        assert_eq!(capped(24_000_000_000), U256::from(24_000_000_000u64));
+        assert_eq!(capped(500_000_000_000), U256::from(100_000_000_000u64));
+    }
+
+    #[test]
+    fn saturating_u64_should_cap() {
+        assert_eq!(saturating_u64(U256::from(21_000)), 21_000);
+        assert_eq!(
+            saturating_u64(U256::from(u64::max_value())),
+            u64::max_value()
+        );
+        assert_eq!(
+            saturating_u64(U256::from(u64::max_value()) + 1),
+            u64::max_value()
+        );
+        // low_u64 would give 1
+        assert_eq!(saturating_u64((U256::one() << 64) + 1), u64::max_value());
+    }
+
+    #[test]
+    fn parse_fee_history_should_work() {
+        let history = json!({
+            "oldestBlock": "0x10",
+            "baseFeePerGas": ["0x3b9aca00", "0x3b9aca00", "0x4a817c800"],
+            "gasUsedRatio": [0.5, 0.9],
+            "reward": [["0x3b9aca00"], ["0x77359400"], ["0x5f5e100"]],
+        });
+        assert_eq!(
+            parse_fee_history(&history),
+            Some(U256::from(20_000_000_000u64 + 1_000_000_000u64))
+        );
+        assert_eq!(parse_fee_history(&json!({})), None);
+    }
+}
@@ -2,18 +2,64 @@ use dotenv::dotenv;
 use env_logger;
 
 mod config;
+mod deduplication;
+mod endpoints;
+mod error;
 mod ethereum_event_handler;
+mod ethereum_events;
 mod ethereum_transactions;
+mod gas;
+mod nonce_manager;
+mod storage;
 mod substrate_event_handler;
 mod substrate_transactions;
+mod supervisor;
+mod transaction_tracker;
+
+use error::Error;
+
+const USAGE: &str = "usage: validator [check-config] [--config <path>]";
+
+enum Command {
+    Run,
+    // prints the resolved config without the keys
+    CheckConfig,
+}
 
 fn main() {
     env_logger::init();
     dotenv().ok();
 
-    let config = config::Config::load().expect("can not load config");
+    let (command, config_path) = parse_args(std::env::args().skip(1)).unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        std::process::exit(2);
+    });
+
+    let config = match config::Config::load(config_path.as_ref().map(String::as_str)) {
+        Ok(config) => config,
+        Err(errors) => {
+            for error in errors {
+                log::error!("[config] {}", error);
+            }
+            std::process::exit(1);
+        }
+    };
+
+    if let Command::CheckConfig = command {
+        println!("{:#?}", config.redacted());
+        return;
+    }
+
+    if let Err(err) = start(config) {
+        log::error!("[validator] can not start, reason: {}", err);
+        std::process::exit(1);
+    }
+}
 
-    log::info!("[ethereum] api url: {:?}", config.eth_api_url);
+// runs the event handlers, they are restarted by their supervisors when they fail
+fn start(config: config::Config) -> Result<(), Error> {
+    log::info!("[ethereum] api urls: {:?}", config.eth_api_urls);
+    log::info!("[ethereum] chain id: {:?}", config.eth_chain_id);
     log::info!(
         "[ethereum] validator address: {:?}",
         config.eth_validator_address
@@ -22,16 +68,81 @@ fn main() {
         "[ethereum] contract address: {:?}",
         config.eth_contract_address
     );
+    log::info!("[substrate] api urls: {:?}", config.sub_api_urls);
+
+    let storage = storage::Storage::open(&config.storage_path).map_err(Error::Storage)?;
     log::info!(
-        "[ethereum] hash of RelayMessage: {:?}",
-        config.eth_relay_message_hash
+        "[storage] path: {:?}, last ethereum block: {:?}, last substrate block: {:?}",
+        config.storage_path,
+        storage.eth_block()?,
+        storage.sub_block()?
     );
+
+    let (_eloop, transport) =
+        web3::transports::WebSocket::new(&endpoints::select(&config.eth_api_urls))?;
+    let web3 = web3::Web3::new(transport);
+
+    // signed transactions are invalid on another chain
+    let node_chain_id = ethereum_transactions::chain_id(&web3)?;
+    if node_chain_id != config.eth_chain_id {
+        return Err(Error::ChainIdMismatch {
+            configured: config.eth_chain_id,
+            node: node_chain_id,
+        });
+    }
+
+    // both handlers send transactions from the same ethereum account
+    let nonce_manager = nonce_manager::NonceManager::new();
+    let tracker = transaction_tracker::TransactionTracker::start(
+        config.clone(),
+        storage.clone(),
+        nonce_manager.clone(),
+    )?;
+
     log::info!(
-        "[ethereum] hash of ApprovedRelayMessage: {:?}",
-        config.eth_approved_relay_message_hash
+        "[ethereum] gas price strategy: {:?}",
+        config.eth_gas_price_strategy
     );
-    log::info!("[substrate] api url: {:?}", config.sub_api_url);
 
-    let _substrate_event_handler = substrate_event_handler::start(config.clone());
-    ethereum_event_handler::start(config);
+    let substrate_event_handler = {
+        let config = config.clone();
+        let storage = storage.clone();
+        let nonce_manager = nonce_manager.clone();
+        let tracker = tracker.clone();
+        supervisor::spawn("substrate_event_handler", move || {
+            substrate_event_handler::start(
+                config.clone(),
+                storage.clone(),
+                nonce_manager.clone(),
+                tracker.clone(),
+            )
+        })?
+    };
+    let ethereum_event_handler = supervisor::spawn("ethereum_event_handler", move || {
+        ethereum_event_handler::start(
+            config.clone(),
+            storage.clone(),
+            nonce_manager.clone(),
+            tracker.clone(),
+        )
+    })?;
+
+    // supervised tasks run until the process is stopped
+    let _ = substrate_event_handler.join();
+    let _ = ethereum_event_handler.join();
+    Ok(())
+}
+
+// command and path of the config file, None if the arguments are invalid
+fn parse_args(mut args: impl Iterator<Item = String>) -> Option<(Command, Option<String>)> {
+    let mut command = Command::Run;
+    let mut config_path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "check-config" => command = Command::CheckConfig,
+            "--config" => config_path = Some(args.next()?),
+            _ => return None,
+        }
+    }
+    Some((command, config_path))
 }
@@ -0,0 +1,348 @@
+use futures::{
+    future::{self, Either, Loop},
+    sync::oneshot,
+    Future,
+};
+use web3::types::{Address, BlockNumber, Bytes, H256, U256};
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::error::Error;
+
+// attempts to send a transaction after the nonce is rejected by the node
+const MAX_RESYNCS: usize = 3;
+
+#[derive(Default)]
+struct AccountState {
+    // nonce of the next transaction, None if it has to be read from the node
+    next_nonce: Option<U256>,
+    // resolved when the last submission of the account is finished
+    last_submission: Option<oneshot::Receiver<()>>,
+}
+
+/// Nonces of the validator accounts shared by all event handlers.
+/// Each handler sends its transactions one after another on its own runtime, but the handlers
+/// run in separate threads and send from the same account. Submissions of an account are
+/// serialized across them: the nonce is taken, the transaction is signed and sent before
+/// the next submission starts. Nonces are tracked locally and resynced from the pending block
+/// of the node when it rejects one.
+#[derive(Clone, Default)]
+pub struct NonceManager {
+    accounts: Arc<Mutex<HashMap<Address, AccountState>>>,
+}
+
+impl NonceManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends the transaction built by `build` for the next nonce of the account.
+    /// Resolves to the transaction hash and the used nonce.
+    pub fn send<T, F>(
+        &self,
+        web3: web3::Web3<T>,
+        account: Address,
+        build: F,
+    ) -> impl Future<Item = (H256, U256), Error = Error>
+    where
+        T: web3::Transport + Clone + Send + 'static,
+        T::Out: Send,
+        F: Fn(U256) -> Result<Vec<u8>, Error> + Send + Sync + 'static,
+    {
+        let (previous, finished) = self.enqueue(account);
+        let manager = self.clone();
+        let build = Arc::new(build);
+        previous
+            .then(move |_| {
+                future::loop_fn(0, move |resyncs| {
+                    let manager = manager.clone();
+                    let web3 = web3.clone();
+                    let build = build.clone();
+                    let tx = manager
+                        .next_nonce(&web3, account)
+                        .from_err()
+                        .and_then(move |nonce| build(nonce).map(|tx| (nonce, tx)));
+                    tx.and_then(move |(nonce, tx)| {
+                        web3.eth()
+                            .send_raw_transaction(Bytes::from(tx))
+                            .then(move |result| match result {
+                                Ok(hash) => {
+                                    manager.set_next_nonce(account, Some(nonce + 1));
+                                    Ok(Loop::Break((hash, nonce)))
+                                }
+                                Err(err) => {
+                                    // the node knows better which nonce is next
+                                    manager.set_next_nonce(account, None);
+                                    if is_nonce_error(&err) && resyncs < MAX_RESYNCS {
+                                        log::warn!(
+                                            "[ethereum] nonce {:?} of {:?} is rejected, resyncing: {:?}",
+                                            nonce,
+                                            account,
+                                            err
+                                        );
+                                        Ok(Loop::Continue(resyncs + 1))
+                                    } else {
+                                        Err(Error::from(err))
+                                    }
+                                }
+                            })
+                    })
+                })
+            })
+            .then(move |result| {
+                // lets the next submission of the account start
+                let _ = finished.send(());
+                result
+            })
+    }
+
+    /// The next nonce of the account is read from the node again,
+    /// its transaction with the tracked nonce is dropped.
+    pub fn resync(&self, account: Address) {
+        self.set_next_nonce(account, None);
+    }
+
+    fn enqueue(
+        &self,
+        account: Address,
+    ) -> (impl Future<Item = (), Error = ()>, oneshot::Sender<()>) {
+        let (finished, submission) = oneshot::channel();
+        let mut accounts = self
+            .accounts
+            .lock()
+            .expect("nonce manager lock is poisoned");
+        let previous = accounts
+            .entry(account)
+            .or_default()
+            .last_submission
+            .replace(submission);
+        let previous = match previous {
+            // the sender is dropped if the previous submission is dropped, it is finished too
+            Some(previous) => Either::A(previous.then(|_| Ok(()))),
+            None => Either::B(future::ok(())),
+        };
+        (previous, finished)
+    }
+
+    fn next_nonce<T>(
+        &self,
+        web3: &web3::Web3<T>,
+        account: Address,
+    ) -> impl Future<Item = U256, Error = web3::Error>
+    where
+        T: web3::Transport,
+    {
+        let local = self
+            .accounts
+            .lock()
+            .expect("nonce manager lock is poisoned")
+            .get(&account)
+            .and_then(|state| state.next_nonce);
+        match local {
+            Some(nonce) => Either::A(future::ok(nonce)),
+            None => Either::B(
+                web3.eth()
+                    .transaction_count(account, Some(BlockNumber::Pending))
+                    .map(move |nonce| {
+                        log::info!("[ethereum] synced nonce of {:?}: {:?}", account, nonce);
+                        nonce
+                    }),
+            ),
+        }
+    }
+
+    fn set_next_nonce(&self, account: Address, nonce: Option<U256>) {
+        self.accounts
+            .lock()
+            .expect("nonce manager lock is poisoned")
+            .entry(account)
+            .or_default()
+            .next_nonce = nonce;
+    }
+}
+
+fn is_nonce_error(err: &web3::Error) -> bool {
+    let message = format!("{:?}", err).to_lowercase();
+    message.contains("nonce too low") || message.contains("replacement transaction underpriced")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core as rpc;
+    use serde_json::json;
+    use std::collections::VecDeque;
+
+    // answers the requests with the scripted responses in order
+    #[derive(Debug, Clone, Default)]
+    struct MockTransport {
+        responses: Arc<Mutex<VecDeque<Result<rpc::Value, rpc::Error>>>>,
+        methods: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MockTransport {
+        fn new(responses: Vec<Result<rpc::Value, rpc::Error>>) -> Self {
+            MockTransport {
+                responses: Arc::new(Mutex::new(responses.into_iter().collect())),
+                methods: Arc::default(),
+            }
+        }
+
+        fn methods(&self) -> Vec<String> {
+            self.methods.lock().unwrap().clone()
+        }
+    }
+
+    impl web3::Transport for MockTransport {
+        type Out = future::FutureResult<rpc::Value, web3::Error>;
+
+        fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (web3::RequestId, rpc::Call) {
+            self.methods.lock().unwrap().push(method.to_string());
+            let call = rpc::Call::MethodCall(rpc::MethodCall {
+                jsonrpc: Some(rpc::Version::V2),
+                method: method.to_string(),
+                params: rpc::Params::Array(params),
+                id: rpc::Id::Num(1),
+            });
+            (1, call)
+        }
+
+        fn send(&self, _id: web3::RequestId, _request: rpc::Call) -> Self::Out {
+            let response = self
+                .responses
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("unexpected request");
+            future::result(response.map_err(web3::Error::Rpc))
+        }
+    }
+
+    fn rpc_error(message: &str) -> rpc::Error {
+        rpc::Error {
+            code: rpc::ErrorCode::ServerError(-32000),
+            message: message.to_string(),
+            data: None,
+        }
+    }
+
+    fn hash(n: u64) -> Result<rpc::Value, rpc::Error> {
+        Ok(json!(format!("0x{:064x}", n)))
+    }
+
+    // records the nonces the transactions are built for
+    fn builder(nonces: Arc<Mutex<Vec<U256>>>) -> impl Fn(U256) -> Result<Vec<u8>, Error> {
+        move |nonce| {
+            nonces.lock().unwrap().push(nonce);
+            Ok(vec![1, 2, 3])
+        }
+    }
+
+    #[test]
+    fn nonce_errors_should_be_recognized() {
+        let error = |message: &str| web3::Error::Rpc(rpc_error(message));
+        assert!(is_nonce_error(&error("nonce too low")));
+        assert!(is_nonce_error(&error("Nonce too low")));
+        assert!(is_nonce_error(&error(
+            "replacement transaction underpriced"
+        )));
+        assert!(!is_nonce_error(&error(
+            "insufficient funds for gas * price + value"
+        )));
+        assert!(!is_nonce_error(&web3::Error::Transport(
+            "connection reset".into()
+        )));
+    }
+
+    #[test]
+    fn submissions_of_account_should_be_serialized() {
+        let transport = MockTransport::new(vec![Ok(json!("0x5")), hash(1), hash(2)]);
+        let web3 = web3::Web3::new(transport.clone());
+        let manager = NonceManager::new();
+        let account = Address::from_low_u64_be(1);
+        let nonces = Arc::new(Mutex::new(vec![]));
+
+        let first = manager.send(web3.clone(), account, builder(nonces.clone()));
+        let second = manager.send(web3, account, builder(nonces.clone()));
+        // the second submission is polled first but waits for the first one
+        let (second, first) = second.join(first).wait().unwrap();
+
+        assert_eq!(first, (H256::from_low_u64_be(1), U256::from(5)));
+        assert_eq!(second, (H256::from_low_u64_be(2), U256::from(6)));
+        assert_eq!(*nonces.lock().unwrap(), vec![U256::from(5), U256::from(6)]);
+        assert_eq!(
+            transport.methods(),
+            vec![
+                "eth_getTransactionCount",
+                "eth_sendRawTransaction",
+                "eth_sendRawTransaction"
+            ]
+        );
+    }
+
+    #[test]
+    fn nonce_should_be_resynced_after_rejection() {
+        let transport = MockTransport::new(vec![
+            Ok(json!("0x5")),
+            Err(rpc_error("nonce too low")),
+            Ok(json!("0x7")),
+            hash(1),
+            hash(2),
+        ]);
+        let web3 = web3::Web3::new(transport.clone());
+        let manager = NonceManager::new();
+        let account = Address::from_low_u64_be(1);
+        let nonces = Arc::new(Mutex::new(vec![]));
+
+        let sent = manager
+            .send(web3.clone(), account, builder(nonces.clone()))
+            .wait()
+            .unwrap();
+        assert_eq!(sent, (H256::from_low_u64_be(1), U256::from(7)));
+
+        let sent = manager
+            .send(web3, account, builder(nonces.clone()))
+            .wait()
+            .unwrap();
+        assert_eq!(sent, (H256::from_low_u64_be(2), U256::from(8)));
+
+        assert_eq!(
+            *nonces.lock().unwrap(),
+            vec![U256::from(5), U256::from(7), U256::from(8)]
+        );
+        assert_eq!(
+            transport.methods(),
+            vec![
+                "eth_getTransactionCount",
+                "eth_sendRawTransaction",
+                "eth_getTransactionCount",
+                "eth_sendRawTransaction",
+                "eth_sendRawTransaction"
+            ]
+        );
+    }
+
+    #[test]
+    fn other_errors_should_fail_the_submission() {
+        let transport = MockTransport::new(vec![
+            Ok(json!("0x5")),
+            Err(rpc_error("insufficient funds for gas * price + value")),
+            Ok(json!("0x5")),
+            hash(1),
+        ]);
+        let web3 = web3::Web3::new(transport.clone());
+        let manager = NonceManager::new();
+        let account = Address::from_low_u64_be(1);
+        let nonces = Arc::new(Mutex::new(vec![]));
+
+        let failed = manager
+            .send(web3.clone(), account, builder(nonces.clone()))
+            .wait();
+        assert!(failed.is_err());
+
+        // the nonce is read from the node again
+        let sent = manager.send(web3, account, builder(nonces)).wait().unwrap();
+        assert_eq!(sent, (H256::from_low_u64_be(1), U256::from(5)));
+    }
+}
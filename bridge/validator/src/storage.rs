@@ -0,0 +1,286 @@
+use parity_codec::{Decode, Encode};
+use primitives::H256;
+use sled;
+
+use crate::error::Error;
+
+const ETH_BLOCK_KEY: &[u8] = b"eth_block";
+const SUB_BLOCK_KEY: &[u8] = b"sub_block";
+const ACTION_PREFIX: &[u8] = b"action:";
+const WITHDRAWN_PREFIX: &[u8] = b"withdrawn:";
+
+/// transactions sent by the validator in response to bridge messages
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    EthApproveTransfer,
+    EthWithdrawTransfer,
+    EthConfirmTransfer,
+    SubMint,
+    SubApproveTransfer,
+    SubConfirmTransfer,
+}
+
+/// state of the sent transaction, ethereum transactions are tracked until they are mined
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum ActionState {
+    Sent,
+    Mined,
+    // reason of the revert
+    Reverted(String),
+    // the message is canceled before the action is taken
+    Canceled,
+    // the transaction is lost by the node or not mined after all gas price bumps,
+    // the action is taken again when the message is handled next time
+    Dropped,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ActionRecord {
+    pub action: Action,
+    pub state: ActionState,
+    // hash of the sent transaction or extrinsic
+    pub tx_hash: Option<H256>,
+}
+
+/// Local database of the validator: the last processed blocks of both chains,
+/// so that the missed blocks are backfilled after restart, and the actions taken per message id.
+#[derive(Clone)]
+pub struct Storage {
+    db: sled::Db,
+}
+
+impl Storage {
+    pub fn open(path: &str) -> Result<Self, &'static str> {
+        let db = sled::Db::open(path).map_err(|_| "can not open validator storage")?;
+        Ok(Storage { db })
+    }
+
+    pub fn eth_block(&self) -> Result<Option<u64>, Error> {
+        self.get(ETH_BLOCK_KEY)
+    }
+
+    pub fn set_eth_block(&self, number: u64) -> Result<(), Error> {
+        self.set_block(ETH_BLOCK_KEY, number)
+    }
+
+    pub fn sub_block(&self) -> Result<Option<u64>, Error> {
+        self.get(SUB_BLOCK_KEY)
+    }
+
+    pub fn set_sub_block(&self, number: u64) -> Result<(), Error> {
+        self.set_block(SUB_BLOCK_KEY, number)
+    }
+
+    pub fn action(&self, message_id: &H256, action: Action) -> Result<Option<ActionRecord>, Error> {
+        self.get(&action_key(message_id, action))
+    }
+
+    pub fn record_action(&self, message_id: &H256, record: ActionRecord) -> Result<(), Error> {
+        self.set(&action_key(message_id, record.action), &record)
+    }
+
+    /// the WithdrawMessage of the transfer is emitted by the contract
+    pub fn is_withdrawn(&self, message_id: &H256) -> Result<bool, Error> {
+        Ok(self
+            .get::<bool>(&withdrawn_key(message_id))?
+            .unwrap_or(false))
+    }
+
+    pub fn set_withdrawn(&self, message_id: &H256) -> Result<(), Error> {
+        self.set(&withdrawn_key(message_id), &true)
+    }
+
+    /// actions whose transactions are sent but their outcome is not known yet
+    pub fn sent_actions(&self) -> Result<Vec<(H256, ActionRecord)>, Error> {
+        let mut sent = vec![];
+        for item in self.db.scan_prefix(ACTION_PREFIX) {
+            let (key, value) = item.map_err(|err| {
+                log::error!("[storage] can not read actions: {:?}", err);
+                Error::Storage("can not read validator storage")
+            })?;
+            let record = ActionRecord::decode(&mut &value[..])
+                .ok_or(Error::Storage("can not decode validator storage value"))?;
+            if record.state == ActionState::Sent {
+                // the key ends with the action byte
+                let message_id = H256::from_slice(&key[ACTION_PREFIX.len()..key.len() - 1]);
+                sent.push((message_id, record));
+            }
+        }
+        Ok(sent)
+    }
+
+    // processed blocks never go back, logs and events of the same block come one by one
+    fn set_block(&self, key: &[u8], number: u64) -> Result<(), Error> {
+        if self.get::<u64>(key)?.map_or(true, |last| number > last) {
+            self.set(key, &number)?;
+        }
+        Ok(())
+    }
+
+    // errors are not taken for missing values, the validator would act on a message twice
+    fn get<V: Decode>(&self, key: &[u8]) -> Result<Option<V>, Error> {
+        let value = self.db.get(key).map_err(|err| {
+            log::error!("[storage] can not read {:?}: {:?}", key, err);
+            Error::Storage("can not read validator storage")
+        })?;
+        match value {
+            Some(value) => V::decode(&mut &value[..])
+                .map(Some)
+                .ok_or(Error::Storage("can not decode validator storage value")),
+            None => Ok(None),
+        }
+    }
+
+    fn set<V: Encode>(&self, key: &[u8], value: &V) -> Result<(), Error> {
+        self.db
+            .insert(key, value.encode())
+            .and_then(|_| self.db.flush())
+            .map(|_| ())
+            .map_err(|err| {
+                log::error!("[storage] can not write {:?}: {:?}", key, err);
+                Error::Storage("can not write validator storage")
+            })
+    }
+}
+
+fn action_key(message_id: &H256, action: Action) -> Vec<u8> {
+    let mut key = ACTION_PREFIX.to_vec();
+    key.extend_from_slice(message_id.as_bytes());
+    key.push(action as u8);
+    key
+}
+
+fn withdrawn_key(message_id: &H256) -> Vec<u8> {
+    let mut key = WITHDRAWN_PREFIX.to_vec();
+    key.extend_from_slice(message_id.as_bytes());
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    fn temporary_storage(name: &str) -> (Storage, std::path::PathBuf) {
+        let path =
+            std::env::temp_dir().join(format!("validator-storage-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        let storage = Storage::open(path.to_str().unwrap()).expect("can open storage");
+        (storage, path)
+    }
+
+    #[test]
+    fn blocks_should_be_stored() {
+        let (storage, path) = temporary_storage("blocks");
+
+        assert_eq!(storage.eth_block().unwrap(), None);
+        storage.set_eth_block(5).unwrap();
+        // processed blocks never go back
+        storage.set_eth_block(4).unwrap();
+        assert_eq!(storage.eth_block().unwrap(), Some(5));
+
+        storage.set_sub_block(7).unwrap();
+        assert_eq!(storage.sub_block().unwrap(), Some(7));
+        assert_eq!(storage.eth_block().unwrap(), Some(5));
+
+        drop(storage);
+        let _ = fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn actions_should_be_stored() {
+        let (storage, path) = temporary_storage("actions");
+        let message_id = H256::repeat_byte(1);
+        let record = ActionRecord {
+            action: Action::EthApproveTransfer,
+            state: ActionState::Reverted("not enough votes".to_string()),
+            tx_hash: Some(H256::repeat_byte(2)),
+        };
+
+        assert_eq!(
+            storage
+                .action(&message_id, Action::EthApproveTransfer)
+                .unwrap(),
+            None
+        );
+        storage.record_action(&message_id, record.clone()).unwrap();
+        assert_eq!(
+            storage
+                .action(&message_id, Action::EthApproveTransfer)
+                .unwrap(),
+            Some(record)
+        );
+        // actions of a message are stored separately
+        assert_eq!(storage.action(&message_id, Action::SubMint).unwrap(), None);
+
+        drop(storage);
+        let _ = fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn withdrawn_transfers_should_be_stored() {
+        let (storage, path) = temporary_storage("withdrawn");
+
+        assert!(!storage.is_withdrawn(&H256::repeat_byte(1)).unwrap());
+        storage.set_withdrawn(&H256::repeat_byte(1)).unwrap();
+        assert!(storage.is_withdrawn(&H256::repeat_byte(1)).unwrap());
+        assert!(!storage.is_withdrawn(&H256::repeat_byte(2)).unwrap());
+        // the withdrawal is not an action of the validator
+        assert_eq!(storage.sent_actions().unwrap(), vec![]);
+
+        drop(storage);
+        let _ = fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn sent_actions_should_be_listed() {
+        let (storage, path) = temporary_storage("sent");
+        let record = |action, state| ActionRecord {
+            action,
+            state,
+            tx_hash: Some(H256::repeat_byte(9)),
+        };
+        storage
+            .record_action(
+                &H256::repeat_byte(1),
+                record(Action::EthApproveTransfer, ActionState::Sent),
+            )
+            .unwrap();
+        storage
+            .record_action(
+                &H256::repeat_byte(1),
+                record(Action::SubMint, ActionState::Mined),
+            )
+            .unwrap();
+        storage
+            .record_action(
+                &H256::repeat_byte(2),
+                record(Action::EthConfirmTransfer, ActionState::Dropped),
+            )
+            .unwrap();
+        storage.set_eth_block(5).unwrap();
+
+        assert_eq!(
+            storage.sent_actions().unwrap(),
+            vec![(
+                H256::repeat_byte(1),
+                record(Action::EthApproveTransfer, ActionState::Sent)
+            )]
+        );
+
+        drop(storage);
+        let _ = fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn undecodable_value_should_fail() {
+        let (storage, path) = temporary_storage("undecodable");
+        storage.db.insert(ETH_BLOCK_KEY, vec![1u8]).unwrap();
+
+        assert!(storage.eth_block().is_err());
+
+        drop(storage);
+        let _ = fs::remove_dir_all(path);
+    }
+}
@@ -1,9 +1,11 @@
 use ethabi;
+use futures::future::{self, Either};
 use log;
+use tokio::runtime::Runtime;
 use web3::{
     contract::tokens::Tokenizable,
     futures::Future,
-    types::{Address, Bytes, H256, U256},
+    types::{Address, H256, U256},
 };
 
 use node_runtime::{bridge, Event};
@@ -12,146 +14,264 @@ use primitives;
 use substrate_api_client::{hexstr_to_vec, Api};
 use system;
 
+use std::panic;
 use std::sync::mpsc;
 use std::thread;
 
 use crate::config;
+use crate::deduplication;
+use crate::endpoints;
+use crate::error::Error;
 use crate::ethereum_transactions;
+use crate::gas::{self, SharedGasPriceOracle};
+use crate::nonce_manager::NonceManager;
+use crate::storage::{Action, ActionRecord, ActionState, Storage};
 use crate::substrate_transactions;
+use crate::transaction_tracker::{self, TransactionTracker};
 
-const AMOUNT: u64 = 0;
-
-pub fn start(config: config::Config) -> thread::JoinHandle<()> {
-    thread::Builder::new()
-        .name("substrate_event_handler".to_string())
-        .spawn(move || {
-            let _sentinel = Sentinel::new(config.clone());
-            let _ = thread::spawn(move || {
-                log::info!("[substrate] starting event_handler");
-                let (events_in, events_out) = mpsc::channel();
-
-                let event_subscriber =
-                    start_event_subscriber(config.sub_api_url.clone(), events_in);
-                let event_handler = start_event_handler(config, events_out);
+/// Handles bridge events of finalized substrate blocks, returns when the subscription
+/// or one of the nodes fails.
+pub fn start(
+    config: config::Config,
+    storage: Storage,
+    nonce_manager: NonceManager,
+    tracker: TransactionTracker,
+) -> Result<(), Error> {
+    log::info!("[substrate] starting event_handler");
+    let (events_in, events_out) = mpsc::channel();
 
-                let _ = event_subscriber.join();
-                let _ = event_handler.join();
-            })
-            .join();
-        })
-        .expect("can not start substrate_event_handler")
+    let event_subscriber = start_event_subscriber(
+        endpoints::select(&config.sub_api_urls),
+        storage.sub_block()?,
+        events_in,
+    )?;
+    // the subscriber stops at the next block once the handler fails and drops the receiver
+    let handled = handle_events(config, storage, nonce_manager, tracker, events_out);
+    let subscribed = event_subscriber
+        .join()
+        .unwrap_or_else(|panic| panic::resume_unwind(panic));
+    handled.and(subscribed)
 }
 
 fn start_event_subscriber(
     api_url: String,
-    events_in: mpsc::Sender<String>,
-) -> thread::JoinHandle<()> {
+    last_block: Option<u64>,
+    events_in: mpsc::Sender<(u64, String)>,
+) -> Result<thread::JoinHandle<Result<(), Error>>, Error> {
+    let subscription_api = Api::new(api_url.clone());
     let mut sub_api = Api::new(api_url);
-    sub_api.init();
+    sub_api.init()?;
 
     log::info!("[substrate] starting subscriber of event_handler");
     thread::Builder::new()
         .name("event_subscriber".to_string())
         .spawn(move || {
-            sub_api.subscribe_events(events_in.clone());
+            let (heads_in, heads_out) = mpsc::channel();
+            let subscription = thread::Builder::new()
+                .name("finalized_heads".to_string())
+                .spawn(move || subscription_api.subscribe_finalized_heads(heads_in))
+                .map_err(Error::Thread)?;
+
+            // only finalized blocks are handled, events of best blocks may be reverted.
+            // Finalized blocks skipped by the subscription and the ones finalized
+            // while the validator was down are caught up in order.
+            let mut next_block = last_block.map(|number| number + 1);
+            for finalized_block in heads_out {
+                let from_block = next_block.unwrap_or(finalized_block);
+                if from_block > finalized_block {
+                    continue;
+                }
+                if from_block < finalized_block {
+                    log::info!(
+                        "[substrate] catching up events of finalized blocks {}..={}",
+                        from_block,
+                        finalized_block
+                    );
+                }
+                for number in from_block..=finalized_block {
+                    if !send_block_events(&sub_api, number, &events_in)? {
+                        return Ok(());
+                    }
+                }
+                next_block = Some(finalized_block + 1);
+            }
+            // the subscription is closed, its error is the reason
+            subscription
+                .join()
+                .unwrap_or_else(|panic| panic::resume_unwind(panic))
         })
-        .expect("can not start event_subscriber")
+        .map_err(Error::Thread)
 }
 
-fn start_event_handler(
+// the hash of a finalized block number never changes, events are read at that block.
+// false if the handler is stopped.
+fn send_block_events(
+    sub_api: &Api,
+    number: u64,
+    events_in: &mpsc::Sender<(u64, String)>,
+) -> Result<bool, Error> {
+    let hash = sub_api
+        .get_block_hash(number)?
+        .ok_or_else(|| Error::InvalidData(format!("finalized block {} is not imported", number)))?;
+    let events = sub_api.get_storage_at("System", "Events", None, hash)?;
+    Ok(events_in.send((number, events)).is_ok())
+}
+
+fn handle_events(
     config: config::Config,
-    events_out: mpsc::Receiver<String>,
-) -> thread::JoinHandle<()> {
+    storage: Storage,
+    nonce_manager: NonceManager,
+    tracker: TransactionTracker,
+    events_out: mpsc::Receiver<(u64, String)>,
+) -> Result<(), Error> {
     log::info!("[substrate] starting handler of event_handler");
-    thread::Builder::new()
-        .name("event_handler".to_string())
-        .spawn(move || {
-            let mut sub_api = Api::new(config.sub_api_url.clone());
-            sub_api.init();
+    let mut sub_api = Api::new(endpoints::select(&config.sub_api_urls));
+    sub_api.init()?;
 
-            let (_eloop, transport) =
-                web3::transports::WebSocket::new(&config.eth_api_url).unwrap();
-            let web3 = web3::Web3::new(transport);
+    let (_eloop, transport) =
+        web3::transports::WebSocket::new(&endpoints::select(&config.eth_api_urls))?;
+    let web3 = web3::Web3::new(transport);
+    // the oracle of a node uses the connection of this run, it is recreated on restart
+    let gas_price_oracle = gas::oracle(&config, web3.clone());
 
-            let abi = ethabi::Contract::load(
-                include_bytes!("../res/EthContract.abi").to_vec().as_slice(),
-            )
-            .expect("can read ABI");
+    let abi = ethabi::Contract::load(include_bytes!("../res/EthContract.abi").to_vec().as_slice())?;
+    let mut runtime = Runtime::new().map_err(Error::Thread)?;
 
-            for event in events_out {
-                log::debug!("[substrate] got event: {:?}", event);
+    // the block is stored as handled once the actions of all its events are sent,
+    // a failed action stops the handler and the block is handled again after the restart
+    for (block_number, event) in events_out {
+        log::debug!(
+            "[substrate] got event of block {}: {:?}",
+            block_number,
+            event
+        );
 
-                let unhex = hexstr_to_vec(event);
-                let mut er_enc = unhex.as_slice();
-                let events = Vec::<system::EventRecord<Event>>::decode(&mut er_enc);
+        let events = hexstr_to_vec(event)
+            .ok()
+            .and_then(|unhex| Vec::<system::EventRecord<Event>>::decode(&mut unhex.as_slice()));
 
-                match events {
-                    Some(evts) => {
-                        for evr in &evts {
-                            log::debug!(
-                                "[substrate] decoded: phase {:?} event {:?}",
-                                evr.phase,
-                                evr.event
-                            );
-                            match &evr.event {
-                                Event::bridge(br) => {
-                                    log::info!("[substrate] bridge event: {:?}", br);
-                                    match &br {
-                                        bridge::RawEvent::RelayMessage(message_id) => {
-                                            handle_replay_message(&sub_api, &config, message_id)
-                                        }
-                                        bridge::RawEvent::ApprovedRelayMessage(
-                                            message_id,
-                                            from,
-                                            to,
-                                            amount,
-                                        ) => handle_approved_relay_message(
-                                            &web3, &abi, &config, message_id, from, to, *amount,
-                                        ),
-                                        bridge::RawEvent::Burned(
-                                            _message_id,
-                                            _from,
-                                            _to,
-                                            _amount,
-                                        ) => (),
-                                        bridge::RawEvent::Minted(message_id) => {
-                                            handle_minted(&web3, &abi, &config, message_id)
-                                        }
-                                    }
+        match events {
+            Some(evts) => {
+                for evr in &evts {
+                    log::debug!(
+                        "[substrate] decoded: phase {:?} event {:?}",
+                        evr.phase,
+                        evr.event
+                    );
+                    match &evr.event {
+                        Event::bridge(br) => {
+                            log::info!("[substrate] bridge event: {:?}", br);
+                            match &br {
+                                bridge::RawEvent::RelayMessage(message_id) => {
+                                    handle_replay_message(&sub_api, &config, &storage, message_id)?
                                 }
+                                bridge::RawEvent::ApprovedRelayMessage(
+                                    message_id,
+                                    from,
+                                    to,
+                                    amount,
+                                ) => handle_approved_relay_message(
+                                    &mut runtime,
+                                    &web3,
+                                    &abi,
+                                    &config,
+                                    &storage,
+                                    &nonce_manager,
+                                    &tracker,
+                                    &gas_price_oracle,
+                                    message_id,
+                                    from,
+                                    to,
+                                    *amount,
+                                )?,
+                                bridge::RawEvent::Burned(_message_id, _from, _to, _amount) => (),
+                                bridge::RawEvent::Minted(message_id) => handle_minted(
+                                    &mut runtime,
+                                    &web3,
+                                    &abi,
+                                    &config,
+                                    &storage,
+                                    &nonce_manager,
+                                    &tracker,
+                                    &gas_price_oracle,
+                                    message_id,
+                                )?,
                                 _ => log::debug!(
-                                    "[substrate] ignoring unsupported module event: {:?}",
-                                    evr.event
+                                    "[substrate] ignoring unsupported bridge event: {:?}",
+                                    br
                                 ),
                             }
                         }
+                        _ => log::debug!(
+                            "[substrate] ignoring unsupported module event: {:?}",
+                            evr.event
+                        ),
                     }
-                    None => log::error!("[substrate] could not decode event record list"),
                 }
             }
-        })
-        .expect("can not start event_handler")
+            None => log::error!("[substrate] could not decode event record list"),
+        }
+        storage.set_sub_block(block_number)?;
+    }
+    Ok(())
 }
 
-fn handle_replay_message(sub_api: &Api, config: &config::Config, message_id: &primitives::H256) {
+fn handle_replay_message(
+    sub_api: &Api,
+    config: &config::Config,
+    storage: &Storage,
+    message_id: &primitives::H256,
+) -> Result<(), Error> {
+    if deduplication::already_done(storage, message_id, Action::SubApproveTransfer)?
+        || deduplication::substrate_proposal_is_closed(
+            sub_api,
+            message_id,
+            Action::SubApproveTransfer,
+        )
+    {
+        return Ok(());
+    }
+
     substrate_transactions::approve_transfer(
         &sub_api,
         config.sub_validator_mnemonic_phrase.clone(),
         *message_id,
-    );
+    )
+    .map_err(|err| {
+        log::warn!(
+            "[substrate] can not call approve_transfer({:?}), reason: {}",
+            message_id,
+            err
+        );
+        err
+    })?;
     log::info!("[substrate] called approve_transfer({:?})", message_id);
+    storage.record_action(
+        message_id,
+        ActionRecord {
+            action: Action::SubApproveTransfer,
+            state: ActionState::Sent,
+            tx_hash: None,
+        },
+    )
 }
 
 fn handle_approved_relay_message<T>(
+    runtime: &mut Runtime,
     web3: &web3::Web3<T>,
     abi: &ethabi::Contract,
     config: &config::Config,
+    storage: &Storage,
+    nonce_manager: &NonceManager,
+    tracker: &TransactionTracker,
+    gas_price_oracle: &SharedGasPriceOracle,
     message_id: &primitives::H256,
     from: &primitives::sr25519::Public,
     to: &primitives::H160,
     amount: u64,
-) where
-    T: web3::Transport + Send + 'static,
+) -> Result<(), Error>
+where
+    T: web3::Transport + Clone + Send + 'static,
     T::Out: Send,
 {
     let args = (
@@ -162,97 +282,111 @@ fn handle_approved_relay_message<T>(
         Address::from(to.as_fixed_bytes()).into_token(),
         U256::from(amount).into_token(),
     );
-    let web3 = web3.clone();
-    let eth_validator_private_key = config.eth_validator_private_key.clone();
-    let eth_contract_address = config.eth_contract_address;
-    let eth_gas_price = config.eth_gas_price;
-    let eth_gas = config.eth_gas;
-    let data =
-        ethereum_transactions::build_transaction_data(&abi, "withdrawTransfer", args.clone());
-    let fut = web3.eth().transaction_count(config.eth_validator_address, None)
-        .and_then(move |nonce| {
-            let tx = ethereum_transactions::build(eth_validator_private_key, eth_contract_address, nonce, AMOUNT, eth_gas_price, eth_gas, data);
-            log::debug!("raw withdrawTransfer: {:?}", tx);
-            web3.eth().send_raw_transaction(Bytes::from(tx))
-                .then(move |res| {
-                    match res {
-                        Ok(tx_res) => {
-                            log::info!("[ethereum] called withdrawTransfer({:?}, {:?}, {:?}, {:?}), nonce: {:?}, result: {:?}",
-                                       args.0, args.1, args.2, args.3, nonce, tx_res)
-                        },
-                        Err(err) => {
-                            log::warn!("can not send withdrawTransfer({:?}, {:?}, {:?}, {:?}), nonce: {:?}, reason: {:?}",
-                                       args.0, args.1, args.2, args.3, nonce, err);
-
-                        }
-                    }
+    if deduplication::already_done(storage, message_id, Action::EthWithdrawTransfer)? {
+        return Ok(());
+    }
 
-                    Ok(())
-                })
-        })
-        .or_else(|e| {
-            log::warn!("can not get nonce: {:?}", e);
-            Ok(())
-        });
-    tokio::run(fut);
+    let message_id = *message_id;
+    let data =
+        ethereum_transactions::build_transaction_data(&abi, "withdrawTransfer", args.clone())?;
+    let vote_is_needed = deduplication::ethereum_withdrawal_is_needed(
+        web3,
+        abi,
+        config,
+        storage,
+        message_id,
+        data.clone(),
+    );
+    let send = {
+        let web3 = web3.clone();
+        let gas_price_oracle = gas_price_oracle.clone();
+        let nonce_manager = nonce_manager.clone();
+        let tracker = tracker.clone();
+        let storage = storage.clone();
+        let config = config.clone();
+        move || {
+            log::info!(
+                "[ethereum] calling withdrawTransfer({:?}, {:?}, {:?}, {:?})",
+                args.0,
+                args.1,
+                args.2,
+                args.3
+            );
+            transaction_tracker::send_tracked(
+                web3,
+                gas_price_oracle,
+                nonce_manager,
+                tracker,
+                storage,
+                &config,
+                message_id,
+                Action::EthWithdrawTransfer,
+                data,
+            )
+        }
+    };
+    let fut = vote_is_needed.and_then(move |needed| {
+        if needed {
+            Either::A(send())
+        } else {
+            Either::B(future::ok(()))
+        }
+    });
+    runtime.block_on(fut)
 }
 
 fn handle_minted<T>(
+    runtime: &mut Runtime,
     web3: &web3::Web3<T>,
     abi: &ethabi::Contract,
     config: &config::Config,
+    storage: &Storage,
+    nonce_manager: &NonceManager,
+    tracker: &TransactionTracker,
+    gas_price_oracle: &SharedGasPriceOracle,
     message_id: &primitives::H256,
-) where
-    T: web3::Transport + Send + 'static,
+) -> Result<(), Error>
+where
+    T: web3::Transport + Clone + Send + 'static,
     T::Out: Send,
 {
     let args = (H256::from(message_id.as_fixed_bytes()).into_token(),);
-
-    let web3 = web3.clone();
-    let eth_validator_private_key = config.eth_validator_private_key.clone();
-    let eth_contract_address = config.eth_contract_address;
-    let eth_gas_price = config.eth_gas_price;
-    let eth_gas = config.eth_gas;
-    let data = ethereum_transactions::build_transaction_data(&abi, "confirmTransfer", args.clone());
-    let fut = web3.eth().transaction_count(config.eth_validator_address, None)
-        .and_then(move |nonce| {
-            let tx = ethereum_transactions::build(eth_validator_private_key, eth_contract_address, nonce, AMOUNT, eth_gas_price, eth_gas, data);
-            log::debug!("raw confirmTransfer: {:?}", tx);
-            web3.eth().send_raw_transaction(Bytes::from(tx))
-                .then(move |res| {
-                    match res {
-                        Ok(tx_res) => {
-                            log::info!("[ethereum] called confirmTransfer({:?}), nonce: {:?}, result: {:?}",
-                                       args.0, nonce, tx_res)
-                        },
-                        Err(err) => {
-                            log::info!("[ethereum] can not send confirmTransfer({:?}), nonce: {:?}, reason: {:?}",
-                                       args.0, nonce, err)
-                        }
-                    }
-
-                    Ok(())
-                })
-        })
-        .or_else(|e| {
-            log::warn!("can not get nonce: {:?}", e);
-            Ok(())
-        });
-    tokio::run(fut);
-}
-
-struct Sentinel {
-    config: config::Config,
-}
-
-impl Sentinel {
-    fn new(config: config::Config) -> Self {
-        Sentinel { config }
+    if deduplication::already_done(storage, message_id, Action::EthConfirmTransfer)? {
+        return Ok(());
     }
-}
 
-impl Drop for Sentinel {
-    fn drop(&mut self) {
-        start(self.config.clone());
-    }
+    let message_id = *message_id;
+    let data =
+        ethereum_transactions::build_transaction_data(&abi, "confirmTransfer", args.clone())?;
+    let vote_is_needed = deduplication::ethereum_vote_is_needed(web3, abi, config, data.clone());
+    let send = {
+        let web3 = web3.clone();
+        let gas_price_oracle = gas_price_oracle.clone();
+        let nonce_manager = nonce_manager.clone();
+        let tracker = tracker.clone();
+        let storage = storage.clone();
+        let config = config.clone();
+        move || {
+            log::info!("[ethereum] calling confirmTransfer({:?})", args.0);
+            transaction_tracker::send_tracked(
+                web3,
+                gas_price_oracle,
+                nonce_manager,
+                tracker,
+                storage,
+                &config,
+                message_id,
+                Action::EthConfirmTransfer,
+                data,
+            )
+        }
+    };
+    let fut = vote_is_needed.and_then(move |needed| {
+        if needed {
+            Either::A(send())
+        } else {
+            Either::B(future::ok(()))
+        }
+    });
+    runtime.block_on(fut)
 }
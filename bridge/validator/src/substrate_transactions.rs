@@ -10,6 +10,8 @@ use runtime_primitives::generic::Era;
 
 use std::sync::Arc;
 
+use crate::error::Error;
+
 pub fn mint(
     sub_api: Arc<Api>,
     signer_mnemonic_phrase: String,
@@ -17,49 +19,53 @@ pub fn mint(
     from: primitives::H160,
     to: AccountId,
     amount: u64,
-) {
+) -> Result<(), Error> {
     let xthex = build_mint(
         &sub_api,
-        get_sr25519_pair(&signer_mnemonic_phrase),
+        get_sr25519_pair(&signer_mnemonic_phrase)?,
         message_id,
         from,
         to,
         amount,
-    );
+    )?;
     //send and watch extrinsic until finalized
-    let _tx_hash = sub_api.send_extrinsic(xthex);
+    let _tx_hash = sub_api.send_extrinsic(xthex)?;
+    Ok(())
 }
 
 pub fn approve_transfer(
     sub_api: &Api,
     signer_mnemonic_phrase: String,
     message_id: primitives::H256,
-) {
+) -> Result<(), Error> {
     let xthex = build_approve_transfer(
         &sub_api,
-        get_sr25519_pair(&signer_mnemonic_phrase),
+        get_sr25519_pair(&signer_mnemonic_phrase)?,
         message_id,
-    );
+    )?;
     //send and watch extrinsic until finalized
-    let _tx_hash = sub_api.send_extrinsic(xthex);
+    let _tx_hash = sub_api.send_extrinsic(xthex)?;
+    Ok(())
 }
 
 pub fn confirm_transfer(
     sub_api: &Api,
     signer_mnemonic_phrase: String,
     message_id: primitives::H256,
-) {
+) -> Result<(), Error> {
     let xthex = build_confirm_transfer(
         &sub_api,
-        get_sr25519_pair(&signer_mnemonic_phrase),
+        get_sr25519_pair(&signer_mnemonic_phrase)?,
         message_id,
-    );
+    )?;
     //send and watch extrinsic until finalized
-    let _tx_hash = sub_api.send_extrinsic(xthex);
+    let _tx_hash = sub_api.send_extrinsic(xthex)?;
+    Ok(())
 }
 
-fn get_sr25519_pair(signer_mnemonic_phrase: &str) -> sr25519::Pair {
-    sr25519::Pair::from_phrase(signer_mnemonic_phrase, None).expect("invalid menemonic phrase")
+fn get_sr25519_pair(signer_mnemonic_phrase: &str) -> Result<sr25519::Pair, Error> {
+    sr25519::Pair::from_phrase(signer_mnemonic_phrase, None)
+        .map_err(|_| Error::Key("invalid mnemonic phrase"))
 }
 
 pub fn build_mint(
@@ -69,9 +75,9 @@ pub fn build_mint(
     from: H160,
     to: AccountId,
     amount: u64,
-) -> String {
-    let signer_index = signer_index(sub_api, &signer);
-    let genesis_hash = sub_api.genesis_hash.expect("can not get genesiss hash");
+) -> Result<String, Error> {
+    let signer_index = signer_index(sub_api, &signer)?;
+    let genesis_hash = sub_api.genesis_hash.ok_or(Error::ApiNotInitialized)?;
     let function = Call::Bridge(BridgeCall::multi_signed_mint(message_id, from, to, amount));
     let era = Era::immortal();
 
@@ -90,12 +96,16 @@ pub fn build_mint(
 
     let mut xthex: String = ext.encode().to_hex();
     xthex.insert_str(0, "0x");
-    xthex
+    Ok(xthex)
 }
 
-pub fn build_approve_transfer(sub_api: &Api, signer: sr25519::Pair, message_id: H256) -> String {
-    let signer_index = signer_index(sub_api, &signer);
-    let genesis_hash = sub_api.genesis_hash.expect("can not get genesiss hash");
+pub fn build_approve_transfer(
+    sub_api: &Api,
+    signer: sr25519::Pair,
+    message_id: H256,
+) -> Result<String, Error> {
+    let signer_index = signer_index(sub_api, &signer)?;
+    let genesis_hash = sub_api.genesis_hash.ok_or(Error::ApiNotInitialized)?;
     let function = Call::Bridge(BridgeCall::approve_transfer(message_id));
     let era = Era::immortal();
 
@@ -114,12 +124,16 @@ pub fn build_approve_transfer(sub_api: &Api, signer: sr25519::Pair, message_id:
 
     let mut xthex: String = ext.encode().to_hex();
     xthex.insert_str(0, "0x");
-    xthex
+    Ok(xthex)
 }
 
-pub fn build_confirm_transfer(sub_api: &Api, signer: sr25519::Pair, message_id: H256) -> String {
-    let signer_index = signer_index(sub_api, &signer);
-    let genesis_hash = sub_api.genesis_hash.expect("can not get genesiss hash");
+pub fn build_confirm_transfer(
+    sub_api: &Api,
+    signer: sr25519::Pair,
+    message_id: H256,
+) -> Result<String, Error> {
+    let signer_index = signer_index(sub_api, &signer)?;
+    let genesis_hash = sub_api.genesis_hash.ok_or(Error::ApiNotInitialized)?;
     let function = Call::Bridge(BridgeCall::confirm_transfer(message_id));
     let era = Era::immortal();
 
@@ -138,16 +152,14 @@ pub fn build_confirm_transfer(sub_api: &Api, signer: sr25519::Pair, message_id:
 
     let mut xthex: String = ext.encode().to_hex();
     xthex.insert_str(0, "0x");
-    xthex
+    Ok(xthex)
 }
 
-fn signer_index(sub_api: &Api, signer: &sr25519::Pair) -> u64 {
+fn signer_index(sub_api: &Api, signer: &sr25519::Pair) -> Result<u64, Error> {
     let account_id = signer.public();
-    let result_str = sub_api
-        .get_storage("System", "AccountNonce", Some(account_id.encode()))
-        .expect("can not read account nonce");
-    let nonce = hexstr_to_u256(result_str);
-    nonce.low_u64()
+    let result_str = sub_api.get_storage("System", "AccountNonce", Some(account_id.encode()))?;
+    let nonce = hexstr_to_u256(result_str)?;
+    Ok(nonce.low_u64())
 }
 
 type RawPayload = (Compact<u64>, node_runtime::Call, Era, H256);
@@ -0,0 +1,110 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+// a task running for this long is healthy, its next failure is restarted quickly again
+const HEALTHY_RUN: Duration = Duration::from_secs(300);
+
+/// Runs the task in its own thread and restarts it whenever it returns or panics.
+/// The reason is logged, restarts of a task failing repeatedly are delayed with exponential backoff.
+pub fn spawn<F>(name: &'static str, task: F) -> Result<thread::JoinHandle<()>, Error>
+where
+    F: Fn() -> Result<(), Error> + Send + 'static,
+{
+    thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || supervise(name, task))
+        .map_err(Error::Thread)
+}
+
+fn supervise<F>(name: &str, task: F)
+where
+    F: Fn() -> Result<(), Error>,
+{
+    let mut backoff = Backoff::default();
+    loop {
+        log::info!("[supervisor] starting {}", name);
+        let started = Instant::now();
+        let reason = match panic::catch_unwind(AssertUnwindSafe(|| task())) {
+            Ok(Ok(())) => "stopped".to_string(),
+            Ok(Err(err)) => err.to_string(),
+            Err(panic) => format!("panicked: {}", panic_message(&*panic)),
+        };
+        let delay = backoff.next(started.elapsed());
+        log::error!(
+            "[supervisor] {} failed, reason: {}, restarting in {:?}",
+            name,
+            reason,
+            delay
+        );
+        thread::sleep(delay);
+    }
+}
+
+struct Backoff {
+    delay: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff { delay: MIN_BACKOFF }
+    }
+}
+
+impl Backoff {
+    // delay before the restart of a task which failed after running for `uptime`
+    fn next(&mut self, uptime: Duration) -> Duration {
+        if uptime >= HEALTHY_RUN {
+            self.delay = MIN_BACKOFF;
+        }
+        let delay = self.delay;
+        self.delay = (self.delay * 2).min(MAX_BACKOFF);
+        delay
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_should_double_up_to_max() {
+        let mut backoff = Backoff::default();
+        let delays: Vec<_> = (0..8)
+            .map(|_| backoff.next(Duration::from_secs(0)).as_secs())
+            .collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+    }
+
+    #[test]
+    fn backoff_should_be_reset_after_healthy_run() {
+        let mut backoff = Backoff::default();
+        backoff.next(Duration::from_secs(0));
+        backoff.next(Duration::from_secs(0));
+        assert_eq!(backoff.next(HEALTHY_RUN), MIN_BACKOFF);
+        assert_eq!(backoff.next(Duration::from_secs(0)), MIN_BACKOFF * 2);
+    }
+
+    #[test]
+    fn panic_message_should_work() {
+        let panic = panic::catch_unwind(|| panic!("can not connect")).unwrap_err();
+        assert_eq!(panic_message(&*panic), "can not connect");
+        let panic = panic::catch_unwind(|| panic!("block {}", 1)).unwrap_err();
+        assert_eq!(panic_message(&*panic), "block 1");
+    }
+}
@@ -0,0 +1,628 @@
+use ethabi::{self, ParamType};
+use futures::{
+    future::{self, Either, Loop},
+    stream,
+    sync::mpsc,
+    Async, Future, Stream,
+};
+use tokio::timer::Delay;
+use web3::types::{BlockNumber, Bytes, CallRequest, TransactionId, TransactionReceipt, H256, U256};
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config;
+use crate::endpoints;
+use crate::error::Error;
+use crate::ethereum_transactions::{self, ERROR_SELECTOR};
+use crate::gas::{self, SharedGasPriceOracle};
+use crate::nonce_manager::NonceManager;
+use crate::storage::{Action, ActionRecord, ActionState, Storage};
+use crate::supervisor;
+
+const AMOUNT: u64 = 0;
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+// polls without a receipt before the transaction is re-sent with a higher gas price
+const POLLS_BEFORE_BUMP: u32 = 20;
+const MAX_GAS_PRICE_BUMPS: u32 = 5;
+// polls the node does not know any version of the transaction before it is taken for dropped
+const POLLS_BEFORE_DROP: u32 = 4;
+// nodes replace a pending transaction only if the new gas price is at least 10% higher
+const GAS_PRICE_BUMP_PERCENT: u64 = 20;
+
+/// transaction sent to the bridge contract on behalf of a message
+pub struct PendingTransaction {
+    pub message_id: primitives::H256,
+    pub action: Action,
+    pub nonce: U256,
+    pub gas_price: u64,
+    pub gas: u64,
+    pub data: Vec<u8>,
+    pub tx_hash: H256,
+}
+
+struct TrackingState {
+    tx: PendingTransaction,
+    // hashes of all sent versions of the transaction, any of them can be mined
+    hashes: Vec<H256>,
+    polls: u32,
+    bumps: u32,
+    unknown_polls: u32,
+}
+
+/// Waits for receipts of the sent transactions in the background.
+/// A stuck transaction is replaced by the one with the same nonce and a higher gas price,
+/// the outcome is recorded as the state of the message action in the validator storage.
+/// A transaction lost by the node or not mined after all bumps is recorded as dropped.
+/// Transactions sent before a restart are tracked again from the storage.
+#[derive(Clone)]
+pub struct TransactionTracker {
+    transactions: mpsc::UnboundedSender<PendingTransaction>,
+}
+
+impl TransactionTracker {
+    pub fn start(
+        config: config::Config,
+        storage: Storage,
+        nonce_manager: NonceManager,
+    ) -> Result<Self, Error> {
+        let (transactions, pending) = mpsc::unbounded();
+        // transactions sent while the tracker restarts wait in the channel
+        let pending = Arc::new(Mutex::new(pending));
+        supervisor::spawn("transaction_tracker", move || {
+            run(
+                config.clone(),
+                storage.clone(),
+                nonce_manager.clone(),
+                pending.clone(),
+            )
+        })?;
+        Ok(TransactionTracker { transactions })
+    }
+
+    pub fn track(&self, tx: PendingTransaction) {
+        log::info!(
+            "[ethereum] tracking {:?} of {:?}: {:?}",
+            tx.action,
+            tx.message_id,
+            tx.tx_hash
+        );
+        if self.transactions.unbounded_send(tx).is_err() {
+            log::error!("[ethereum] transaction tracker is stopped");
+        }
+    }
+}
+
+/// Sends the call of the bridge contract for the action of the message from the validator
+/// account. Once the node accepts the transaction the action is recorded as sent
+/// and the transaction is tracked until it is mined.
+pub fn send_tracked<T>(
+    web3: web3::Web3<T>,
+    gas_price_oracle: SharedGasPriceOracle,
+    nonce_manager: NonceManager,
+    tracker: TransactionTracker,
+    storage: Storage,
+    config: &config::Config,
+    message_id: primitives::H256,
+    action: Action,
+    data: Vec<u8>,
+) -> impl Future<Item = (), Error = Error>
+where
+    T: web3::Transport + Clone + Send + 'static,
+    T::Out: Send,
+{
+    let private_key = config.eth_validator_private_key.clone();
+    let validator_address = config.eth_validator_address;
+    let contract_address = config.eth_contract_address;
+    let chain_id = config.eth_chain_id;
+    let tracked_data = data.clone();
+    gas::fees(&web3, &*gas_price_oracle, config, data.clone())
+        .from_err()
+        .and_then(move |(gas, gas_price)| {
+            nonce_manager
+                .send(web3, validator_address, move |nonce| {
+                    let tx = ethereum_transactions::build(
+                        private_key.clone(),
+                        contract_address,
+                        nonce,
+                        AMOUNT,
+                        gas_price,
+                        gas,
+                        data.clone(),
+                        chain_id,
+                    )?;
+                    log::debug!("[ethereum] raw transaction of {:?}: {:?}", action, tx);
+                    Ok(tx)
+                })
+                .map(move |(tx_hash, nonce)| PendingTransaction {
+                    message_id,
+                    action,
+                    nonce,
+                    gas_price,
+                    gas,
+                    data: tracked_data,
+                    tx_hash,
+                })
+        })
+        .then(move |result| match result {
+            Ok(tx) => {
+                log::info!(
+                    "[ethereum] sent {:?} of {:?}, nonce: {:?}, result: {:?}",
+                    action,
+                    message_id,
+                    tx.nonce,
+                    tx.tx_hash
+                );
+                storage.record_action(
+                    &message_id,
+                    ActionRecord {
+                        action,
+                        state: ActionState::Sent,
+                        tx_hash: Some(primitives::H256::from_slice(tx.tx_hash.as_bytes())),
+                    },
+                )?;
+                tracker.track(tx);
+                Ok(())
+            }
+            Err(err) => {
+                log::warn!(
+                    "[ethereum] can not send {:?} of {:?}, reason: {:?}",
+                    action,
+                    message_id,
+                    err
+                );
+                Err(err)
+            }
+        })
+}
+
+fn run(
+    config: config::Config,
+    storage: Storage,
+    nonce_manager: NonceManager,
+    pending: Arc<Mutex<mpsc::UnboundedReceiver<PendingTransaction>>>,
+) -> Result<(), Error> {
+    let (_eloop, transport) =
+        web3::transports::WebSocket::new(&endpoints::select(&config.eth_api_urls))?;
+    let web3 = web3::Web3::new(transport);
+    let config = Arc::new(config);
+
+    let restored = restore(&web3, &config, &storage, &nonce_manager)?;
+    let pending = stream::poll_fn(move || match pending.lock() {
+        Ok(mut pending) => pending.poll(),
+        Err(_) => Ok(Async::Ready(None)),
+    });
+    // a transaction restored from the storage can be sent to the channel as well
+    let tracked = Arc::new(Mutex::new(HashSet::new()));
+    let transactions = stream::iter_ok::<_, ()>(restored).chain(pending);
+    tokio::run(transactions.for_each(move |tx| {
+        let key = (tx.message_id, tx.action);
+        if !tracked
+            .lock()
+            .expect("tracked transactions lock is poisoned")
+            .insert(key)
+        {
+            log::debug!(
+                "[ethereum] {:?} of {:?} is tracked already",
+                tx.action,
+                tx.message_id
+            );
+            return Ok(());
+        }
+        let tracked = tracked.clone();
+        let tracking = track(
+            web3.clone(),
+            config.clone(),
+            storage.clone(),
+            nonce_manager.clone(),
+            tx,
+        );
+        tokio::spawn(tracking.then(move |_| {
+            tracked
+                .lock()
+                .expect("tracked transactions lock is poisoned")
+                .remove(&key);
+            Ok(())
+        }));
+        Ok(())
+    }));
+    Ok(())
+}
+
+// transactions sent before the restart, their tracking is lost with the previous run
+fn restore<T>(
+    web3: &web3::Web3<T>,
+    config: &config::Config,
+    storage: &Storage,
+    nonce_manager: &NonceManager,
+) -> Result<Vec<PendingTransaction>, Error>
+where
+    T: web3::Transport,
+{
+    let mut restored = vec![];
+    for (message_id, record) in storage.sent_actions()? {
+        let tx_hash = match (record.action, record.tx_hash) {
+            (Action::EthApproveTransfer, Some(hash))
+            | (Action::EthWithdrawTransfer, Some(hash))
+            | (Action::EthConfirmTransfer, Some(hash)) => H256::from_slice(hash.as_bytes()),
+            // extrinsics are not tracked
+            _ => continue,
+        };
+        match web3
+            .eth()
+            .transaction(TransactionId::Hash(tx_hash))
+            .wait()?
+        {
+            Some(tx) => restored.push(PendingTransaction {
+                message_id,
+                action: record.action,
+                nonce: tx.nonce,
+                gas_price: gas::saturating_u64(tx.gas_price),
+                gas: gas::saturating_u64(tx.gas),
+                data: tx.input.0,
+                tx_hash,
+            }),
+            None => drop_transaction(
+                config,
+                storage,
+                nonce_manager,
+                message_id,
+                record.action,
+                tx_hash,
+                "the node does not know the transaction sent before the restart",
+            ),
+        }
+    }
+    log::info!("[ethereum] restored {} sent transactions", restored.len());
+    Ok(restored)
+}
+
+fn track<T>(
+    web3: web3::Web3<T>,
+    config: Arc<config::Config>,
+    storage: Storage,
+    nonce_manager: NonceManager,
+    tx: PendingTransaction,
+) -> impl Future<Item = (), Error = ()>
+where
+    T: web3::Transport + Clone + Send + 'static,
+    T::Out: Send,
+{
+    let state = TrackingState {
+        hashes: vec![tx.tx_hash],
+        tx,
+        polls: 0,
+        bumps: 0,
+        unknown_polls: 0,
+    };
+    future::loop_fn(state, move |state| {
+        let web3 = web3.clone();
+        let config = config.clone();
+        let storage = storage.clone();
+        let nonce_manager = nonce_manager.clone();
+        Delay::new(Instant::now() + POLL_INTERVAL)
+            .map_err(|e| log::error!("[ethereum] transaction tracker timer failed: {:?}", e))
+            .and_then(move |_| {
+                find_receipt(&web3, state.hashes.clone()).and_then(move |receipt| match receipt {
+                    Some(receipt) => Either::A(
+                        finish(&web3, &config, &storage, state.tx, receipt).map(Loop::Break),
+                    ),
+                    None => Either::B(is_known(&web3, state.hashes.clone()).and_then(
+                        move |known| {
+                            next_poll(&web3, &config, &storage, &nonce_manager, state, known)
+                        },
+                    )),
+                })
+            })
+    })
+}
+
+// the transaction is re-sent with a higher gas price when it is stuck,
+// tracking stops once it can not be mined anymore
+fn next_poll<T>(
+    web3: &web3::Web3<T>,
+    config: &config::Config,
+    storage: &Storage,
+    nonce_manager: &NonceManager,
+    mut state: TrackingState,
+    known: bool,
+) -> impl Future<Item = Loop<(), TrackingState>, Error = ()>
+where
+    T: web3::Transport,
+{
+    state.polls += 1;
+    state.unknown_polls = if known { 0 } else { state.unknown_polls + 1 };
+    let can_bump =
+        state.bumps < MAX_GAS_PRICE_BUMPS && state.tx.gas_price < config.eth_max_gas_price;
+
+    let dropped = if state.unknown_polls >= POLLS_BEFORE_DROP {
+        Some("the node does not know the transaction")
+    } else if state.polls >= POLLS_BEFORE_BUMP && !can_bump {
+        Some("the transaction is not mined after all gas price bumps")
+    } else {
+        None
+    };
+    if let Some(reason) = dropped {
+        drop_transaction(
+            config,
+            storage,
+            nonce_manager,
+            state.tx.message_id,
+            state.tx.action,
+            state.tx.tx_hash,
+            reason,
+        );
+        return Either::A(future::ok(Loop::Break(())));
+    }
+
+    if state.polls >= POLLS_BEFORE_BUMP {
+        Either::B(Either::A(
+            resend(web3, config, storage, state).map(Loop::Continue),
+        ))
+    } else {
+        Either::B(Either::B(future::ok(Loop::Continue(state))))
+    }
+}
+
+// any sent version of the transaction is known to the node, failed requests are taken for known
+fn is_known<T>(web3: &web3::Web3<T>, hashes: Vec<H256>) -> impl Future<Item = bool, Error = ()>
+where
+    T: web3::Transport,
+{
+    let transactions = hashes
+        .into_iter()
+        .map(|hash| web3.eth().transaction(TransactionId::Hash(hash)))
+        .collect::<Vec<_>>();
+    future::join_all(transactions).then(|result| match result {
+        Ok(transactions) => Ok(transactions.iter().any(Option::is_some)),
+        Err(err) => {
+            log::warn!("[ethereum] can not get transaction: {:?}", err);
+            Ok(true)
+        }
+    })
+}
+
+// receipt of the mined version of the transaction, failed polls are retried by the next one
+fn find_receipt<T>(
+    web3: &web3::Web3<T>,
+    hashes: Vec<H256>,
+) -> impl Future<Item = Option<TransactionReceipt>, Error = ()>
+where
+    T: web3::Transport,
+{
+    let receipts = hashes
+        .into_iter()
+        .map(|hash| web3.eth().transaction_receipt(hash))
+        .collect::<Vec<_>>();
+    future::join_all(receipts).then(|result| match result {
+        Ok(receipts) => Ok(receipts.into_iter().filter_map(|receipt| receipt).next()),
+        Err(err) => {
+            log::warn!("[ethereum] can not get transaction receipt: {:?}", err);
+            Ok(None)
+        }
+    })
+}
+
+fn finish<T>(
+    web3: &web3::Web3<T>,
+    config: &config::Config,
+    storage: &Storage,
+    tx: PendingTransaction,
+    receipt: TransactionReceipt,
+) -> impl Future<Item = (), Error = ()>
+where
+    T: web3::Transport,
+{
+    let message_id = tx.message_id;
+    let action = tx.action;
+    let tx_hash = receipt.transaction_hash;
+    let storage = storage.clone();
+    let record = move |state: ActionState| {
+        let record = ActionRecord {
+            action,
+            state,
+            tx_hash: Some(primitives::H256::from_slice(tx_hash.as_bytes())),
+        };
+        if let Err(err) = storage.record_action(&message_id, record) {
+            log::error!(
+                "[ethereum] can not record the outcome of {:?} of {:?}: {}",
+                action,
+                message_id,
+                err
+            );
+        }
+    };
+
+    // transactions of blocks before Byzantium have no status
+    let succeeded = receipt.status.map_or(true, |status| !status.is_zero());
+    if succeeded {
+        log::info!(
+            "[ethereum] {:?} of {:?} is mined: {:?}",
+            action,
+            message_id,
+            tx_hash
+        );
+        record(ActionState::Mined);
+        return Either::A(future::ok(()));
+    }
+
+    let block = receipt
+        .block_number
+        .map(|number| BlockNumber::Number(number.low_u64().saturating_sub(1)));
+    let call = CallRequest {
+        from: Some(config.eth_validator_address),
+        to: config.eth_contract_address,
+        gas: Some(U256::from(config.eth_gas)),
+        gas_price: None,
+        value: None,
+        data: Some(Bytes::from(tx.data)),
+    };
+    // the call is replayed to get the reason, receipts do not contain it
+    Either::B(web3.eth().call(call, block).then(move |result| {
+        let reason = match result {
+            Ok(output) => decode_revert_reason(&output.0),
+            Err(err) => format!("{:?}", err),
+        };
+        log::warn!(
+            "[ethereum] {:?} of {:?} is reverted: {:?}, reason: {}",
+            action,
+            message_id,
+            tx_hash,
+            reason
+        );
+        record(ActionState::Reverted(reason));
+        Ok(())
+    }))
+}
+
+fn resend<T>(
+    web3: &web3::Web3<T>,
+    config: &config::Config,
+    storage: &Storage,
+    mut state: TrackingState,
+) -> impl Future<Item = TrackingState, Error = ()>
+where
+    T: web3::Transport,
+{
+    let previous = state.tx.gas_price;
+    let gas_price = (previous.saturating_mul(100 + GAS_PRICE_BUMP_PERCENT) / 100)
+        .max(previous + 1)
+        .min(config.eth_max_gas_price);
+    let tx = ethereum_transactions::build(
+        config.eth_validator_private_key.clone(),
+        config.eth_contract_address,
+        state.tx.nonce,
+        AMOUNT,
+        gas_price,
+        state.tx.gas,
+        state.tx.data.clone(),
+        config.eth_chain_id,
+    );
+    let eth = web3.eth();
+    let storage = storage.clone();
+    future::result(tx)
+        .and_then(move |tx| eth.send_raw_transaction(Bytes::from(tx)).from_err())
+        .then(move |result| {
+            state.polls = 0;
+            state.bumps += 1;
+            match result {
+                Ok(hash) => {
+                    log::info!(
+                        "[ethereum] {:?} of {:?} is re-sent with gas price {}, nonce: {:?}, result: {:?}",
+                        state.tx.action,
+                        state.tx.message_id,
+                        gas_price,
+                        state.tx.nonce,
+                        hash
+                    );
+                    state.tx.gas_price = gas_price;
+                    state.tx.tx_hash = hash;
+                    state.hashes.push(hash);
+                    let record = ActionRecord {
+                        action: state.tx.action,
+                        state: ActionState::Sent,
+                        tx_hash: Some(primitives::H256::from_slice(hash.as_bytes())),
+                    };
+                    if let Err(err) = storage.record_action(&state.tx.message_id, record) {
+                        log::error!(
+                            "[ethereum] can not record re-sent {:?} of {:?}: {}",
+                            state.tx.action,
+                            state.tx.message_id,
+                            err
+                        );
+                    }
+                }
+                // the previous version could be mined meanwhile, its receipt is polled further
+                Err(err) => log::warn!(
+                    "[ethereum] can not re-send {:?} of {:?}, nonce: {:?}, reason: {:?}",
+                    state.tx.action,
+                    state.tx.message_id,
+                    state.tx.nonce,
+                    err
+                ),
+            }
+            Ok(state)
+        })
+}
+
+// the action is taken again when its message is handled next time, the nonce of the dropped
+// transaction is free or used by another one, so the next nonce is read from the node
+fn drop_transaction(
+    config: &config::Config,
+    storage: &Storage,
+    nonce_manager: &NonceManager,
+    message_id: primitives::H256,
+    action: Action,
+    tx_hash: H256,
+    reason: &str,
+) {
+    log::warn!(
+        "[ethereum] {:?} of {:?} is dropped: {:?}, reason: {}",
+        action,
+        message_id,
+        tx_hash,
+        reason
+    );
+    nonce_manager.resync(config.eth_validator_address);
+    let record = ActionRecord {
+        action,
+        state: ActionState::Dropped,
+        tx_hash: Some(primitives::H256::from_slice(tx_hash.as_bytes())),
+    };
+    if let Err(err) = storage.record_action(&message_id, record) {
+        log::error!(
+            "[ethereum] can not record dropped {:?} of {:?}: {}",
+            action,
+            message_id,
+            err
+        );
+    }
+}
+
+fn decode_revert_reason(output: &[u8]) -> String {
+    if !output.starts_with(&ERROR_SELECTOR) {
+        return "unknown".to_string();
+    }
+    ethabi::decode(&[ParamType::String], &output[ERROR_SELECTOR.len()..])
+        .ok()
+        .and_then(|tokens| tokens.into_iter().next())
+        .and_then(|token| token.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethabi::Token;
+
+    fn error_output(reason: &str) -> Vec<u8> {
+        let mut output = ERROR_SELECTOR.to_vec();
+        output.extend(ethabi::encode(&[Token::String(reason.to_string())]));
+        output
+    }
+
+    #[test]
+    fn revert_reason_should_be_decoded() {
+        assert_eq!(
+            decode_revert_reason(&error_output("Not enough votes")),
+            "Not enough votes"
+        );
+        assert_eq!(decode_revert_reason(&error_output("")), "");
+    }
+
+    #[test]
+    fn unknown_revert_reason_should_not_fail() {
+        // reverted without a reason
+        assert_eq!(decode_revert_reason(&[]), "unknown");
+        // output of another function
+        let mut output = error_output("Not enough votes");
+        output[0] = 0;
+        assert_eq!(decode_revert_reason(&output), "unknown");
+        // malformed reason
+        assert_eq!(
+            decode_revert_reason(&error_output("reason")[..40]),
+            "unknown"
+        );
+    }
+}
@@ -4,39 +4,76 @@
 ///
 use crate::token;
 use crate::types::{
-    BridgeMessage, BridgeTransfer, Kind, MemberId, ProposalId, Status, TokenBalance,
-    TransferMessage, ValidatorMessage,
+    v0, BridgeMessage, BridgeTransfer, Kind, MemberId, OperationMessage, ProposalId, Status,
+    TokenBalance, TokenId, TransferMessage, ValidatorMessage,
 };
+use crate::weights;
 use parity_codec::Encode;
 use primitives::H160;
-use runtime_primitives::traits::{As, Hash};
+use rstd::prelude::Vec;
+use runtime_primitives::traits::{As, EnsureOrigin, Hash, Saturating, Zero};
 use support::{
-    decl_event, decl_module, decl_storage, dispatch::Result, ensure, StorageMap, StorageValue,
+    decl_event, decl_module, decl_storage, dispatch::Result, ensure, storage,
+    traits::ReservableCurrency, StorageMap, StorageValue,
 };
 use system::{self, ensure_signed};
 
 const MAX_VALIDATORS: u32 = 100_000;
+/// added to the priority of valid validator votes in the transaction pool
+pub const VOTE_PRIORITY: u64 = 1_000_000;
+/// current layout of the module storage, see `migrate`
+const STORAGE_VERSION: u32 = 1;
+/// number of proposals migrated in one block
+const MIGRATION_BATCH: ProposalId = 100;
 
 decl_event!(
     pub enum Event<T>
     where
         AccountId = <T as system::Trait>::AccountId,
         Hash = <T as system::Trait>::Hash,
+        BlockNumber = <T as system::Trait>::BlockNumber,
     {
         RelayMessage(Hash),
         ApprovedRelayMessage(Hash, AccountId, H160, TokenBalance),
         Minted(Hash),
         Burned(Hash, AccountId, H160, TokenBalance),
+        EmergencyPause(AccountId, BlockNumber),
+        PauseExtended(AccountId, BlockNumber),
+        AutoResumed(BlockNumber),
+        ValidatorForceAdded(AccountId),
+        ValidatorForceRemoved(AccountId),
+        BridgeForcePaused,
+        BridgeForceResumed,
+        TransferForceCanceled(Hash, AccountId, TokenBalance),
     }
 );
 
 pub trait Trait: token::Trait + system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// origin allowed to bypass validators voting in emergency, e.g. sudo or council
+    type ForceOrigin: EnsureOrigin<Self::Origin>;
 }
 
 decl_storage! {
     trait Store for Module<T: Trait> as Bridge {
+        StorageVersion get(storage_version): u32;
+        // next proposal to migrate and the number of proposals at the upgrade
+        MigrationProgress get(migration_progress): Option<(ProposalId, ProposalId)>;
         BridgeIsOperational get(bridge_is_operational): bool = true;
+        Guardian get(guardian) config(): T::AccountId;
+        EmergencyPauseDuration get(emergency_pause_duration) config(): T::BlockNumber = T::BlockNumber::sa(600);
+        PauseDeadline get(pause_deadline): Option<T::BlockNumber>;
+        // validators which extended the emergency pause of the epoch
+        PauseExtendedBy get(pause_extended_by): map (T::AccountId, u64) => bool;
+        // number of bridge state changes, keys pause and resume proposals
+        BridgeEpoch get(bridge_epoch): u64;
+
+        DepositsAreOperational get(deposits_are_operational): bool = true;
+        WithdrawalsAreOperational get(withdrawals_are_operational): bool = true;
+        PausedTokens get(token_is_paused): map TokenId => bool;
+        OperationMessages get(operation_messages): map (T::Hash) => OperationMessage<T::AccountId, T::Hash>;
+        OperationEpoch get(operation_epoch): map (Status, TokenId) => u64;
+        ProposedFeeRates get(proposed_fee_rate): map (T::Hash) => TokenBalance;
         BridgeMessages get(bridge_messages): map (T::Hash) => BridgeMessage<T::AccountId, T::Hash>;
 
         BridgeTransfers get(transfers): map ProposalId => BridgeTransfer<T::Hash>;
@@ -44,6 +81,9 @@ decl_storage! {
         TransferMessages get(messages): map(T::Hash) => TransferMessage<T::AccountId, T::Hash>;
         TransferId get(transfer_id_by_hash): map(T::Hash) => ProposalId;
         MessageId get(message_id_by_transfer_id): map(ProposalId) => T::Hash;
+        // native currency reserved by set_transfer, refunded once the transfer is finished
+        TransferDeposit get(transfer_deposit) config(): T::Balance;
+        TransferDeposits get(transfer_deposit_of): map(T::Hash) => T::Balance;
 
 
         ValidatorsCount get(validators_count) config(): u32 = 3;
@@ -62,12 +102,28 @@ decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         fn deposit_event<T>() = default;
 
+        // resume the bridge automatically once the emergency pause deadline is reached
+        fn on_initialize(n: T::BlockNumber) {
+            Self::migrate();
+
+            if let Some(deadline) = Self::pause_deadline() {
+                if n >= deadline {
+                    <BridgeIsOperational<T>>::put(true);
+                    <PauseDeadline<T>>::kill();
+                    Self::next_epoch();
+                    Self::deposit_event(RawEvent::AutoResumed(n));
+                }
+            }
+        }
+
         // initiate substrate -> ethereum transfer.
         // create proposition and emit the RelayMessage event
         fn set_transfer(origin, to: H160, #[compact] amount: TokenBalance)-> Result
         {
             let from = ensure_signed(origin)?;
             ensure!(Self::bridge_is_operational(), "Bridge is not operational");
+            Self::check_withdrawals_are_operational()?;
+            <token::Module<T>>::charge_weight(&from, weights::SET_TRANSFER)?;
 
             let transfer_hash = (&from, &to, amount, T::BlockNumber::sa(0)).using_encoded(<T as system::Trait>::Hashing::hash);
 
@@ -78,8 +134,12 @@ decl_module! {
                 amount,
                 status: Status::Withdraw,
                 action: Status::Withdraw,
+                token_id: <token::Module<T>>::token_default().id,
             };
-            Self::get_transfer_id_checked(transfer_hash, Kind::Transfer)?;
+            // a duplicate must not reserve one more deposit
+            ensure!(!<TransferId<T>>::exists(transfer_hash), "This transfer already open");
+            Self::reserve_deposit(&message.substrate_address, transfer_hash)?;
+            Self::create_transfer(transfer_hash, Kind::Transfer)?;
             Self::deposit_event(RawEvent::RelayMessage(transfer_hash));
 
             <TransferMessages<T>>::insert(transfer_hash, message);
@@ -90,8 +150,10 @@ decl_module! {
         fn multi_signed_mint(origin, message_id: T::Hash, from: H160, to: T::AccountId, #[compact] amount: TokenBalance)-> Result {
             let validator = ensure_signed(origin)?;
             ensure!(Self::bridge_is_operational(), "Bridge is not operational");
+            Self::check_deposits_are_operational()?;
 
-            Self::check_validator(validator)?;
+            Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::MULTI_SIGNED_MINT)?;
 
             if !<TransferMessages<T>>::exists(message_id) {
                 let message = TransferMessage{
@@ -101,6 +163,7 @@ decl_module! {
                     amount,
                     status: Status::Deposit,
                     action: Status::Deposit,
+                    token_id: <token::Module<T>>::token_default().id,
                 };
                 <TransferMessages<T>>::insert(message_id, message);
                 Self::get_transfer_id_checked(message_id, Kind::Transfer)?;
@@ -116,7 +179,9 @@ decl_module! {
         fn approve_transfer(origin, message_id: T::Hash) -> Result {
             let validator = ensure_signed(origin)?;
             ensure!(Self::bridge_is_operational(), "Bridge is not operational");
-            Self::check_validator(validator)?;
+            Self::check_withdrawals_are_operational()?;
+            Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::APPROVE_TRANSFER)?;
 
             let id = <TransferId<T>>::get(message_id);
             Self::_sign(id)
@@ -125,8 +190,8 @@ decl_module! {
         // each validator calls it to add new validator
         fn add_validator(origin, address: T::AccountId) -> Result {
             let validator = ensure_signed(origin)?;
-            ensure!(Self::bridge_is_operational(), "Bridge is not operational");
-            Self::check_validator(validator)?;
+            Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::VOTE)?;
 
             ensure!(<ValidatorsCount<T>>::get() < 100_000, "Validators maximum reached.");
             let hash = ("add", &address).using_encoded(<T as system::Trait>::Hashing::hash);
@@ -148,8 +213,8 @@ decl_module! {
         // each validator calls it to remove new validator
         fn remove_validator(origin, address: T::AccountId) -> Result {
             let validator = ensure_signed(origin)?;
-            ensure!(Self::bridge_is_operational(), "Bridge is not operational");
-            Self::check_validator(validator)?;
+            Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::VOTE)?;
 
             ensure!(<ValidatorsCount<T>>::get() > 1, "Can not remove last validator.");
 
@@ -174,9 +239,13 @@ decl_module! {
         fn pause_bridge(origin) -> Result {
             let validator = ensure_signed(origin)?;
             Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::VOTE)?;
 
-            ensure!(Self::bridge_is_operational(), "Bridge is not operational already");
-            let hash = ("pause", T::BlockNumber::sa(0)).using_encoded(<T as system::Trait>::Hashing::hash);
+            // emergency pause can be made permanent by the quorum
+            let is_emergency_paused = Self::pause_deadline().is_some();
+            ensure!(Self::bridge_is_operational() || is_emergency_paused, "Bridge is not operational already");
+            // the tag differs from the block number keyed proposals of older versions
+            let hash = ("pause_epoch", Self::bridge_epoch()).using_encoded(<T as system::Trait>::Hashing::hash);
 
             if !<BridgeMessages<T>>::exists(hash) {
                 let message = BridgeMessage {
@@ -193,12 +262,54 @@ decl_module! {
             Self::_sign(id)
         }
 
+        // any validator or the guardian calls it to pause the bridge immediately.
+        // the bridge resumes by itself at the deadline unless the quorum pauses it permanently
+        fn emergency_pause(origin) -> Result {
+            let account = ensure_signed(origin)?;
+            let is_guardian = account == Self::guardian();
+            ensure!(
+                is_guardian || <Validators<T>>::exists(account.clone()),
+                "Only validators or guardian can call this function"
+            );
+            ensure!(Self::bridge_is_operational(), "Bridge is not operational already");
+            <token::Module<T>>::charge_weight(&account, weights::EMERGENCY_PAUSE)?;
+
+            let deadline = <system::Module<T>>::block_number() + Self::emergency_pause_duration();
+            <BridgeIsOperational<T>>::put(false);
+            <PauseDeadline<T>>::put(deadline);
+            Self::next_epoch();
+
+            Self::deposit_event(RawEvent::EmergencyPause(account, deadline));
+            Ok(())
+        }
+
+        // each validator can postpone automatic resume of the emergency pause once,
+        // the deadline is moved to the full pause duration from now
+        fn extend_pause(origin) -> Result {
+            let validator = ensure_signed(origin)?;
+            Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::EXTEND_PAUSE)?;
+
+            let deadline = Self::pause_deadline().ok_or("Bridge is not in emergency pause")?;
+            let key = (validator.clone(), Self::bridge_epoch());
+            ensure!(!<PauseExtendedBy<T>>::get(&key), "This validator extended the pause already");
+
+            let now = <system::Module<T>>::block_number();
+            let new_deadline = (now + Self::emergency_pause_duration()).max(deadline);
+            <PauseExtendedBy<T>>::insert(key, true);
+            <PauseDeadline<T>>::put(new_deadline);
+
+            Self::deposit_event(RawEvent::PauseExtended(validator, new_deadline));
+            Ok(())
+        }
+
         // each validator calls it to resume the bridge
         fn resume_bridge(origin) -> Result {
             let validator = ensure_signed(origin)?;
             Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::VOTE)?;
 
-            let hash = ("resume", T::BlockNumber::sa(0)).using_encoded(<T as system::Trait>::Hashing::hash);
+            let hash = ("resume_epoch", Self::bridge_epoch()).using_encoded(<T as system::Trait>::Hashing::hash);
 
             if !<BridgeMessages<T>>::exists(hash) {
                 let message = BridgeMessage {
@@ -215,11 +326,96 @@ decl_module! {
             Self::_sign(id)
         }
 
+        // each validator calls it to pause ethereum -> substrate transfers
+        fn pause_deposits(origin) -> Result {
+            let validator = ensure_signed(origin)?;
+            Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::VOTE)?;
+            ensure!(Self::deposits_are_operational(), "Deposits are paused already");
+
+            Self::propose_operation(validator, Status::PauseDeposits, TokenId::default())
+        }
+
+        // each validator calls it to resume ethereum -> substrate transfers
+        fn resume_deposits(origin) -> Result {
+            let validator = ensure_signed(origin)?;
+            Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::VOTE)?;
+
+            Self::propose_operation(validator, Status::ResumeDeposits, TokenId::default())
+        }
+
+        // each validator calls it to pause substrate -> ethereum transfers
+        fn pause_withdrawals(origin) -> Result {
+            let validator = ensure_signed(origin)?;
+            Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::VOTE)?;
+            ensure!(Self::withdrawals_are_operational(), "Withdrawals are paused already");
+
+            Self::propose_operation(validator, Status::PauseWithdrawals, TokenId::default())
+        }
+
+        // each validator calls it to resume substrate -> ethereum transfers
+        fn resume_withdrawals(origin) -> Result {
+            let validator = ensure_signed(origin)?;
+            Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::VOTE)?;
+
+            Self::propose_operation(validator, Status::ResumeWithdrawals, TokenId::default())
+        }
+
+        // each validator calls it to pause transfers of the token in both directions
+        fn pause_token(origin, token_id: TokenId) -> Result {
+            let validator = ensure_signed(origin)?;
+            Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::VOTE)?;
+            ensure!(!Self::token_is_paused(token_id), "Token is paused already");
+
+            Self::propose_operation(validator, Status::PauseToken, token_id)
+        }
+
+        // each validator calls it to resume transfers of the token
+        fn resume_token(origin, token_id: TokenId) -> Result {
+            let validator = ensure_signed(origin)?;
+            Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::VOTE)?;
+
+            Self::propose_operation(validator, Status::ResumeToken, token_id)
+        }
+
+        // each validator calls it to set the rate of transaction fees paid in tokens
+        fn set_fee_rate(origin, #[compact] rate: TokenBalance) -> Result {
+            let validator = ensure_signed(origin)?;
+            Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::VOTE)?;
+
+            let token_id = <token::Module<T>>::token_default().id;
+            let epoch = Self::operation_epoch((Status::SetFeeRate, token_id));
+            let hash = ("fee_rate", rate, epoch).using_encoded(<T as system::Trait>::Hashing::hash);
+
+            if !<OperationMessages<T>>::exists(hash) {
+                let message = OperationMessage {
+                    message_id: hash,
+                    account: validator,
+                    token_id,
+                    action: Status::SetFeeRate,
+                    status: Status::SetFeeRate,
+                };
+                <OperationMessages<T>>::insert(hash, message);
+                <ProposedFeeRates<T>>::insert(hash, rate);
+                Self::get_transfer_id_checked(hash, Kind::Operation)?;
+            }
+
+            let id = <TransferId<T>>::get(hash);
+            Self::_sign(id)
+        }
+
         //confirm burn from validator
         fn confirm_transfer(origin, message_id: T::Hash) -> Result {
             let validator = ensure_signed(origin)?;
             ensure!(Self::bridge_is_operational(), "Bridge is not operational");
-            Self::check_validator(validator)?;
+            Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::CONFIRM_TRANSFER)?;
 
             let id = <TransferId<T>>::get(message_id);
 
@@ -238,26 +434,158 @@ decl_module! {
         fn cancel_transfer(origin, message_id: T::Hash) -> Result {
             let validator = ensure_signed(origin)?;
             ensure!(Self::bridge_is_operational(), "Bridge is not operational");
-            Self::check_validator(validator)?;
+            Self::check_validator(validator.clone())?;
+            <token::Module<T>>::charge_weight(&validator, weights::CANCEL_TRANSFER)?;
 
             let mut message = <TransferMessages<T>>::get(message_id);
             message.status = Status::Canceled;
 
             <token::Module<T>>::unlock(&message.substrate_address, message.amount)?;
+            Self::refund_deposit(&message.substrate_address, message_id);
             <TransferMessages<T>>::insert(message_id, message);
 
             Ok(())
         }
+
+        // add validator bypassing the voting, e.g. when validators lost their keys
+        fn force_add_validator(origin, address: T::AccountId) -> Result {
+            T::ForceOrigin::ensure_origin(origin)?;
+            ensure!(!<Validators<T>>::exists(address.clone()), "Account is a validator already");
+            ensure!(<ValidatorsCount<T>>::get() < MAX_VALIDATORS, "Validators maximum reached.");
+
+            <Validators<T>>::insert(address.clone(), true);
+            <ValidatorsCount<T>>::mutate(|x| *x += 1);
+
+            Self::deposit_event(RawEvent::ValidatorForceAdded(address));
+            Ok(())
+        }
+
+        // remove validator bypassing the voting, e.g. when its key is compromised
+        fn force_remove_validator(origin, address: T::AccountId) -> Result {
+            T::ForceOrigin::ensure_origin(origin)?;
+            Self::check_validator(address.clone())?;
+            ensure!(<ValidatorsCount<T>>::get() > 1, "Can not remove last validator.");
+
+            <Validators<T>>::remove(address.clone());
+            <ValidatorsCount<T>>::mutate(|x| *x -= 1);
+
+            Self::deposit_event(RawEvent::ValidatorForceRemoved(address));
+            Ok(())
+        }
+
+        // pause the bridge bypassing the voting
+        fn force_pause(origin) -> Result {
+            T::ForceOrigin::ensure_origin(origin)?;
+
+            <BridgeIsOperational<T>>::put(false);
+            <PauseDeadline<T>>::kill();
+            Self::next_epoch();
+
+            Self::deposit_event(RawEvent::BridgeForcePaused);
+            Ok(())
+        }
+
+        // resume the bridge bypassing the voting
+        fn force_resume(origin) -> Result {
+            T::ForceOrigin::ensure_origin(origin)?;
+
+            <BridgeIsOperational<T>>::put(true);
+            <PauseDeadline<T>>::kill();
+            Self::next_epoch();
+
+            Self::deposit_event(RawEvent::BridgeForceResumed);
+            Ok(())
+        }
+
+        // cancel unfinished substrate -> ethereum transfer and unlock its funds
+        fn force_cancel_transfer(origin, message_id: T::Hash) -> Result {
+            T::ForceOrigin::ensure_origin(origin)?;
+            ensure!(<TransferMessages<T>>::exists(message_id), "Transfer does not exist");
+
+            let mut message = <TransferMessages<T>>::get(message_id);
+            ensure!(message.action == Status::Withdraw, "Only withdrawals can be canceled");
+            ensure!(
+                message.status != Status::Confirmed && message.status != Status::Canceled,
+                "Transfer is finished already"
+            );
+
+            // funds are locked only after the transfer is approved
+            if message.status == Status::Approved {
+                <token::Module<T>>::unlock(&message.substrate_address, message.amount)?;
+            }
+            message.status = Status::Canceled;
+            Self::refund_deposit(&message.substrate_address, message_id);
+            <TransferMessages<T>>::insert(message_id, message.clone());
+
+            let transfer_id = <TransferId<T>>::get(message_id);
+            <BridgeTransfers<T>>::mutate(transfer_id, |transfer| transfer.open = false);
+
+            Self::deposit_event(RawEvent::TransferForceCanceled(
+                message_id,
+                message.substrate_address,
+                message.amount,
+            ));
+            Ok(())
+        }
     }
 }
 
 impl<T: Trait> Module<T> {
+    /// bring the storage written by previous runtime versions to the current layout
+    fn migrate() {
+        let version = Self::storage_version();
+        if version >= STORAGE_VERSION {
+            return;
+        }
+
+        if version < 1 && !Self::migrate_to_v1() {
+            return;
+        }
+        <StorageVersion<T>>::put(STORAGE_VERSION);
+    }
+
+    /// v1 adds token_id to transfer messages.
+    /// migrates one batch of the proposals created before the upgrade, returns true once all are done
+    fn migrate_to_v1() -> bool {
+        let token_id = <token::Module<T>>::token_default().id;
+        let (from, end) = Self::migration_progress().unwrap_or((0, Self::bridge_transfers_count()));
+        let to = end.min(from.saturating_add(MIGRATION_BATCH));
+        for transfer_id in from..to {
+            let transfer = <BridgeTransfers<T>>::get(transfer_id);
+            if let Kind::Transfer = transfer.kind {
+                let key = <TransferMessages<T>>::key_for(transfer.message_id);
+                let old = storage::get::<v0::TransferMessage<T::AccountId, T::Hash>>(&key);
+                if let Some(old) = old {
+                    let message = TransferMessage {
+                        message_id: old.message_id,
+                        eth_address: old.eth_address,
+                        substrate_address: old.substrate_address,
+                        amount: old.amount,
+                        status: old.status,
+                        action: old.action,
+                        token_id,
+                    };
+                    <TransferMessages<T>>::insert(transfer.message_id, message);
+                }
+            }
+        }
+
+        if to < end {
+            <MigrationProgress<T>>::put((to, end));
+            false
+        } else {
+            <MigrationProgress<T>>::kill();
+            true
+        }
+    }
+
     fn _sign(transfer_id: ProposalId) -> Result {
         let mut transfer = <BridgeTransfers<T>>::get(transfer_id);
 
         let mut message = <TransferMessages<T>>::get(transfer.message_id);
         let mut validator_message = <ValidatorHistory<T>>::get(transfer.message_id);
         let mut bridge_message = <BridgeMessages<T>>::get(transfer.message_id);
+        let mut operation_message = <OperationMessages<T>>::get(transfer.message_id);
         ensure!(transfer.open, "This transfer is not open");
         transfer.votes += 1;
 
@@ -268,12 +596,14 @@ impl<T: Trait> Module<T> {
                     Kind::Transfer => message.status = Status::Approved,
                     Kind::Validator => validator_message.status = Status::Approved,
                     Kind::Bridge => bridge_message.status = Status::Approved,
+                    Kind::Operation => operation_message.status = Status::Approved,
                 },
             }
             match transfer.kind {
                 Kind::Transfer => Self::execute_transfer(message)?,
                 Kind::Validator => Self::manage_validator(validator_message)?,
                 Kind::Bridge => Self::manage_bridge(bridge_message)?,
+                Kind::Operation => Self::manage_operation(operation_message)?,
             }
             transfer.open = false;
         } else {
@@ -303,14 +633,69 @@ impl<T: Trait> Module<T> {
 
     fn pause_the_bridge(message: BridgeMessage<T::AccountId, T::Hash>) -> Result {
         <BridgeIsOperational<T>>::mutate(|x| *x = false);
+        <PauseDeadline<T>>::kill();
+        Self::next_epoch();
         Self::update_status(message.message_id, Status::Confirmed, Kind::Bridge)
     }
 
     fn resume_the_bridge(message: BridgeMessage<T::AccountId, T::Hash>) -> Result {
         <BridgeIsOperational<T>>::mutate(|x| *x = true);
+        <PauseDeadline<T>>::kill();
+        Self::next_epoch();
         Self::update_status(message.message_id, Status::Confirmed, Kind::Bridge)
     }
 
+    /// create or vote for the proposal switching one of the bridge directions or tokens
+    fn propose_operation(validator: T::AccountId, action: Status, token_id: TokenId) -> Result {
+        let epoch = Self::operation_epoch((action.clone(), token_id));
+        let hash = ("operation", &action, token_id, epoch)
+            .using_encoded(<T as system::Trait>::Hashing::hash);
+
+        if !<OperationMessages<T>>::exists(hash) {
+            let message = OperationMessage {
+                message_id: hash,
+                account: validator,
+                token_id,
+                action: action.clone(),
+                status: action,
+            };
+            <OperationMessages<T>>::insert(hash, message);
+            Self::get_transfer_id_checked(hash, Kind::Operation)?;
+        }
+
+        let id = <TransferId<T>>::get(hash);
+        Self::_sign(id)
+    }
+
+    /// ethereum -> substrate transfers of the default token are allowed
+    fn check_deposits_are_operational() -> Result {
+        ensure!(
+            Self::deposits_are_operational(),
+            "Deposits are not operational"
+        );
+        let token_id = <token::Module<T>>::token_default().id;
+        ensure!(!Self::token_is_paused(token_id), "Token is not operational");
+
+        Ok(())
+    }
+
+    /// substrate -> ethereum transfers of the default token are allowed
+    fn check_withdrawals_are_operational() -> Result {
+        ensure!(
+            Self::withdrawals_are_operational(),
+            "Withdrawals are not operational"
+        );
+        let token_id = <token::Module<T>>::token_default().id;
+        ensure!(!Self::token_is_paused(token_id), "Token is not operational");
+
+        Ok(())
+    }
+
+    /// start new pause/resume cycle, so pending proposals of the previous one are abandoned
+    fn next_epoch() {
+        <BridgeEpoch<T>>::mutate(|x| *x = x.wrapping_add(1));
+    }
+
     /// add validator
     fn _add_validator(info: ValidatorMessage<T::AccountId, T::Hash>) -> Result {
         ensure!(<ValidatorsCount<T>>::get() < MAX_VALIDATORS, "Validators maximum reached.");
@@ -340,6 +725,27 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
+    /// reserve native currency of the sender until the transfer is finished
+    fn reserve_deposit(who: &T::AccountId, message_id: T::Hash) -> Result {
+        let deposit = Self::transfer_deposit();
+        if deposit.is_zero() {
+            return Ok(());
+        }
+
+        <balances::Module<T> as ReservableCurrency<T::AccountId>>::reserve(who, deposit)?;
+        <TransferDeposits<T>>::mutate(message_id, |reserved| {
+            *reserved = reserved.saturating_add(deposit)
+        });
+        Ok(())
+    }
+
+    fn refund_deposit(who: &T::AccountId, message_id: T::Hash) {
+        let deposit = <TransferDeposits<T>>::take(message_id);
+        if !deposit.is_zero() {
+            <balances::Module<T> as ReservableCurrency<T::AccountId>>::unreserve(who, deposit);
+        }
+    }
+
     fn execute_burn(message_id: T::Hash) -> Result {
         let message = <TransferMessages<T>>::get(message_id);
         let from = message.substrate_address.clone();
@@ -347,6 +753,7 @@ impl<T: Trait> Module<T> {
 
         <token::Module<T>>::unlock(&from, message.amount)?;
         <token::Module<T>>::_burn(from.clone(), message.amount)?;
+        Self::refund_deposit(&from, message_id);
 
         Self::deposit_event(RawEvent::Burned(message_id, from, to, message.amount));
         Ok(())
@@ -357,7 +764,7 @@ impl<T: Trait> Module<T> {
             Status::Deposit => match message.status {
                 Status::Approved => {
                     let to = message.substrate_address.clone();
-                    <token::Module<T>>::_mint(to, message.amount)?;
+                    <token::Module<T>>::_mint_deposit(to, message.amount)?;
                     Self::deposit_event(RawEvent::Minted(message.message_id));
                     Self::update_status(message.message_id, Status::Confirmed, Kind::Transfer)
                 }
@@ -411,6 +818,29 @@ impl<T: Trait> Module<T> {
         }
     }
 
+    fn manage_operation(message: OperationMessage<T::AccountId, T::Hash>) -> Result {
+        ensure!(
+            message.status == Status::Approved,
+            "Tried to manage operation with non-supported status"
+        );
+        match message.action {
+            Status::PauseDeposits => <DepositsAreOperational<T>>::put(false),
+            Status::ResumeDeposits => <DepositsAreOperational<T>>::put(true),
+            Status::PauseWithdrawals => <WithdrawalsAreOperational<T>>::put(false),
+            Status::ResumeWithdrawals => <WithdrawalsAreOperational<T>>::put(true),
+            Status::PauseToken => <PausedTokens<T>>::insert(message.token_id, true),
+            Status::ResumeToken => <PausedTokens<T>>::remove(message.token_id),
+            Status::SetFeeRate => <token::Module<T>>::set_fee_exchange_rate(
+                Self::proposed_fee_rate(message.message_id),
+            ),
+            _ => return Err("Tried to manage operation with non-supported status"),
+        }
+        <OperationEpoch<T>>::mutate((message.action, message.token_id), |x| {
+            *x = x.wrapping_add(1)
+        });
+        Self::update_status(message.message_id, Status::Confirmed, Kind::Operation)
+    }
+
     fn create_transfer(transfer_hash: T::Hash, kind: Kind) -> Result {
         ensure!(
             !<TransferId<T>>::exists(transfer_hash),
@@ -456,6 +886,11 @@ impl<T: Trait> Module<T> {
                 message.status = status;
                 <BridgeMessages<T>>::insert(id, message);
             }
+            Kind::Operation => {
+                let mut message = <OperationMessages<T>>::get(id);
+                message.status = status;
+                <OperationMessages<T>>::insert(id, message);
+            }
         }
         Ok(())
     }
@@ -470,9 +905,59 @@ impl<T: Trait> Module<T> {
         }
         Ok(())
     }
+    /// transaction pool check of the validator votes, drops votes of non-validators
+    /// and votes for closed proposals before they take block space.
+    /// Returns the tag which deduplicates votes of a validator, other calls have no tag.
+    pub fn check_vote(
+        who: &T::AccountId,
+        call: &Call<T>,
+    ) -> rstd::result::Result<Option<Vec<u8>>, &'static str> {
+        let (vote, message_id) = match call {
+            Call::multi_signed_mint(message_id, _, _, _) => {
+                // the first vote opens the proposal
+                ensure!(
+                    !<TransferId<T>>::exists(message_id) || Self::proposal_is_open(*message_id),
+                    "This transfer is not open"
+                );
+                ("mint", *message_id)
+            }
+            Call::approve_transfer(message_id) => {
+                ensure!(
+                    Self::proposal_is_open(*message_id),
+                    "This transfer is not open"
+                );
+                ("approve", *message_id)
+            }
+            Call::confirm_transfer(message_id) => {
+                let status = <TransferMessages<T>>::get(message_id).status;
+                ensure!(
+                    status == Status::Approved || status == Status::Confirmed,
+                    "This transfer must be approved first."
+                );
+                // confirmation reopens the approved transfer, closed confirmed one is burned
+                ensure!(
+                    status == Status::Approved || Self::proposal_is_open(*message_id),
+                    "This transfer is not open"
+                );
+                ("confirm", *message_id)
+            }
+            _ => return Ok(None),
+        };
+        Self::check_validator(who.clone())?;
+
+        Ok(Some(("bridge_vote", vote, who, message_id).encode()))
+    }
+
+    fn proposal_is_open(message_id: T::Hash) -> bool {
+        <TransferId<T>>::exists(message_id)
+            && <BridgeTransfers<T>>::get(<TransferId<T>>::get(message_id)).open
+    }
+
     fn check_validator(validator: T::AccountId) -> Result {
         let is_trusted = <Validators<T>>::exists(validator);
         ensure!(is_trusted, "Only validators can call this function");
+        // votes would read messages not migrated yet
+        ensure!(Self::migration_progress().is_none(), "Bridge storage is being migrated");
 
         Ok(())
     }
@@ -487,15 +972,26 @@ mod tests {
     use runtime_io::with_externalities;
     use runtime_primitives::{
         testing::{Digest, DigestItem, Header},
-        traits::{BlakeTwo256, IdentityLookup},
+        traits::{BlakeTwo256, IdentityLookup, OnInitialize},
         BuildStorage,
     };
-    use support::{assert_noop, assert_ok, impl_outer_origin};
+    use support::{assert_noop, assert_ok, impl_outer_event, impl_outer_origin};
 
     impl_outer_origin! {
         pub enum Origin for Test {}
     }
 
+    mod bridge {
+        // the module has to be named for `impl_outer_event!`
+        pub use super::super::*;
+    }
+
+    impl_outer_event! {
+        pub enum TestEvent for Test {
+            balances<T>, token<T>, bridge<T>,
+        }
+    }
+
     // For testing the module, we construct most of a mock runtime. This means
     // first constructing a configuration type (`Test`) which `impl`s each of the
     // configuration traits of modules we want to use.
@@ -511,7 +1007,7 @@ mod tests {
         type AccountId = u64;
         type Lookup = IdentityLookup<Self::AccountId>;
         type Header = Header;
-        type Event = ();
+        type Event = TestEvent;
         type Log = DigestItem;
     }
     impl balances::Trait for Test {
@@ -521,21 +1017,33 @@ mod tests {
         type TransactionPayment = ();
         type TransferPayment = ();
         type DustRemoval = ();
-        type Event = ();
+        type Event = TestEvent;
     }
     impl timestamp::Trait for Test {
         type Moment = u64;
         type OnTimestampSet = ();
     }
     impl token::Trait for Test {
-        type Event = ();
+        type Event = TestEvent;
+        type Signature = token::tests::TestSignature;
     }
     impl Trait for Test {
-        type Event = ();
+        type Event = TestEvent;
+        type ForceOrigin = EnsureRoot;
+    }
+
+    pub struct EnsureRoot;
+    impl EnsureOrigin<Origin> for EnsureRoot {
+        type Success = ();
+        fn ensure_origin(o: Origin) -> rstd::result::Result<Self::Success, &'static str> {
+            system::ensure_root(o)
+        }
     }
 
     type BridgeModule = Module<Test>;
     type TokenModule = token::Module<Test>;
+    type Balances = balances::Module<Test>;
+    type System = system::Module<Test>;
 
     const ETH_MESSAGE_ID: &[u8; 32] = b"0x5617efe391571b5dc8230db92ba65b";
     const ETH_ADDRESS: &[u8; 20] = b"0x00b46c2526ebb8f4c9";
@@ -545,6 +1053,9 @@ mod tests {
     const V4: u64 = 4;
     const USER1: u64 = 4;
     const USER2: u64 = 5;
+    const GUARDIAN: u64 = 6;
+    const PAUSE_DURATION: u64 = 10;
+    const DEPOSIT: u128 = 100;
 
     // This function basically just builds a genesis storage key/value store according to
     // our desired mockup.
@@ -579,6 +1090,9 @@ mod tests {
             GenesisConfig::<Test> {
                 validators_count: 3u32,
                 validator_accounts: vec![V1, V2, V3],
+                guardian: GUARDIAN,
+                emergency_pause_duration: PAUSE_DURATION,
+                transfer_deposit: DEPOSIT,
             }
             .build_storage()
             .unwrap()
@@ -623,6 +1137,36 @@ mod tests {
         })
     }
     #[test]
+    fn token_eth2sub_mint_below_minimum_balance_works() {
+        with_externalities(&mut new_test_ext(), || {
+            let message_id = H256::from(ETH_MESSAGE_ID);
+            let eth_address = H160::from(ETH_ADDRESS);
+            assert_ok!(TokenModule::set_minimum_balance(Origin::ROOT, 100));
+
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V2),
+                message_id,
+                eth_address,
+                USER2,
+                99
+            ));
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V1),
+                message_id,
+                eth_address,
+                USER2,
+                99
+            ));
+            // the deposit finishes, its dust is burned without a collector
+            let message = BridgeModule::messages(message_id);
+            assert_eq!(message.status, Status::Confirmed);
+            let transfer = BridgeModule::transfers(0);
+            assert_eq!(transfer.open, false);
+            assert_eq!(TokenModule::balance_of(USER2), 0);
+            assert_eq!(TokenModule::total_supply(), 0);
+        })
+    }
+    #[test]
     fn token_eth2sub_closed_transfer_fail() {
         with_externalities(&mut new_test_ext(), || {
             let message_id = H256::from(ETH_MESSAGE_ID);
@@ -739,6 +1283,88 @@ mod tests {
             assert_eq!(TokenModule::total_supply(), 500);
         })
     }
+    fn bridge_events() -> Vec<Event<Test>> {
+        system::Module::<Test>::events()
+            .into_iter()
+            .filter_map(|record| match record.event {
+                TestEvent::bridge(event) => Some(event),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sub2eth_round_trip_should_end_with_burn() {
+        with_externalities(&mut new_test_ext(), || {
+            let eth_message_id = H256::from(ETH_MESSAGE_ID);
+            let eth_address = H160::from(ETH_ADDRESS);
+
+            //substrate <----- ETH
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V1),
+                eth_message_id,
+                eth_address,
+                USER2,
+                1000
+            ));
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V2),
+                eth_message_id,
+                eth_address,
+                USER2,
+                1000
+            ));
+
+            //substrate ----> ETH, validators react to the events as the validator service does
+            assert_ok!(BridgeModule::set_transfer(
+                Origin::signed(USER2),
+                eth_address,
+                500
+            ));
+            let message_id = bridge_events()
+                .into_iter()
+                .find_map(|event| match event {
+                    RawEvent::RelayMessage(message_id) => Some(message_id),
+                    _ => None,
+                })
+                .expect("RelayMessage is emitted");
+
+            // RelayMessage -> approve_transfer
+            for validator in &[V1, V2] {
+                assert_ok!(BridgeModule::approve_transfer(
+                    Origin::signed(*validator),
+                    message_id
+                ));
+            }
+            assert!(bridge_events().contains(&RawEvent::ApprovedRelayMessage(
+                message_id,
+                USER2,
+                eth_address,
+                500
+            )));
+
+            // ApprovedRelayMessage -> withdrawTransfer on ethereum, which emits WithdrawMessage,
+            // WithdrawMessage -> confirm_transfer
+            for validator in &[V1, V2] {
+                assert_ok!(BridgeModule::confirm_transfer(
+                    Origin::signed(*validator),
+                    message_id
+                ));
+            }
+
+            assert_eq!(BridgeModule::messages(message_id).status, Status::Confirmed);
+            assert!(bridge_events().contains(&RawEvent::Burned(
+                message_id,
+                USER2,
+                eth_address,
+                500
+            )));
+            assert_eq!(TokenModule::locked(USER2), 0);
+            assert_eq!(TokenModule::balance_of(USER2), 500);
+            assert_eq!(TokenModule::total_supply(), 500);
+        })
+    }
+
     #[test]
     fn token_sub2eth_burn_fail_skip_approval() {
         with_externalities(&mut new_test_ext(), || {
@@ -891,4 +1517,931 @@ mod tests {
             assert_eq!(BridgeModule::bridge_is_operational(), true);
         })
     }
+    #[test]
+    fn pause_and_resume_the_bridge_several_times_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            for round in 0..3 {
+                assert_eq!(BridgeModule::bridge_epoch(), 2 * round);
+                assert_ok!(BridgeModule::pause_bridge(Origin::signed(V2)));
+                assert_ok!(BridgeModule::pause_bridge(Origin::signed(V1)));
+                assert_eq!(BridgeModule::bridge_is_operational(), false);
+
+                assert_ok!(BridgeModule::resume_bridge(Origin::signed(V1)));
+                assert_ok!(BridgeModule::resume_bridge(Origin::signed(V3)));
+                assert_eq!(BridgeModule::bridge_is_operational(), true);
+            }
+            assert_eq!(BridgeModule::bridge_transfers_count(), 6);
+        })
+    }
+    #[test]
+    fn pause_proposals_of_each_cycle_are_independent() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(BridgeModule::pause_bridge(Origin::signed(V1)));
+            assert_ok!(BridgeModule::pause_bridge(Origin::signed(V2)));
+            let first_pause = BridgeModule::message_id_by_transfer_id(0);
+
+            assert_ok!(BridgeModule::resume_bridge(Origin::signed(V1)));
+            assert_ok!(BridgeModule::resume_bridge(Origin::signed(V2)));
+
+            // a single vote of the second cycle must not be enough
+            assert_ok!(BridgeModule::pause_bridge(Origin::signed(V3)));
+            assert_eq!(BridgeModule::bridge_is_operational(), true);
+            let second_pause = BridgeModule::message_id_by_transfer_id(2);
+            assert!(first_pause != second_pause);
+            assert_eq!(
+                BridgeModule::bridge_messages(second_pause).status,
+                Status::Pending
+            );
+            assert_eq!(BridgeModule::transfers(2).open, true);
+        })
+    }
+    #[test]
+    fn pause_after_upgrade_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            // closed proposals of the versions before the epoch
+            let old_proposals = vec![
+                ("pause", Status::PauseTheBridge),
+                ("resume", Status::ResumeTheBridge),
+            ];
+            for (id, (tag, action)) in old_proposals.into_iter().enumerate() {
+                let hash = BlakeTwo256::hash_of(&(tag, 0u64));
+                let message = BridgeMessage {
+                    message_id: hash,
+                    account: V1,
+                    action,
+                    status: Status::Confirmed,
+                };
+                <BridgeMessages<Test>>::insert(hash, message);
+                assert_ok!(BridgeModule::create_transfer(hash, Kind::Bridge));
+                <BridgeTransfers<Test>>::mutate(id as ProposalId, |t| t.open = false);
+            }
+
+            assert_ok!(BridgeModule::pause_bridge(Origin::signed(V1)));
+            assert_ok!(BridgeModule::pause_bridge(Origin::signed(V2)));
+            assert_eq!(BridgeModule::bridge_is_operational(), false);
+            assert_ok!(BridgeModule::resume_bridge(Origin::signed(V1)));
+            assert_ok!(BridgeModule::resume_bridge(Origin::signed(V2)));
+            assert_eq!(BridgeModule::bridge_is_operational(), true);
+        })
+    }
+    #[test]
+    fn auto_resume_abandons_pending_resume_proposal() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(BridgeModule::emergency_pause(Origin::signed(V1)));
+            assert_ok!(BridgeModule::resume_bridge(Origin::signed(V1)));
+            BridgeModule::on_initialize(PAUSE_DURATION);
+            assert_eq!(BridgeModule::bridge_is_operational(), true);
+
+            // stale resume vote is not counted in the next cycle
+            assert_ok!(BridgeModule::emergency_pause(Origin::signed(V2)));
+            assert_ok!(BridgeModule::resume_bridge(Origin::signed(V2)));
+            assert_eq!(BridgeModule::bridge_is_operational(), false);
+            assert_ok!(BridgeModule::resume_bridge(Origin::signed(V3)));
+            assert_eq!(BridgeModule::bridge_is_operational(), true);
+        })
+    }
+    #[test]
+    fn emergency_pause_by_single_validator_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            let eth_message_id = H256::from(ETH_MESSAGE_ID);
+            let eth_address = H160::from(ETH_ADDRESS);
+
+            assert_ok!(BridgeModule::emergency_pause(Origin::signed(V1)));
+            assert_eq!(BridgeModule::bridge_is_operational(), false);
+            assert_eq!(BridgeModule::pause_deadline(), Some(PAUSE_DURATION));
+            assert_noop!(
+                BridgeModule::multi_signed_mint(
+                    Origin::signed(V2),
+                    eth_message_id,
+                    eth_address,
+                    USER2,
+                    1000
+                ),
+                "Bridge is not operational"
+            );
+        })
+    }
+    #[test]
+    fn emergency_pause_by_guardian_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(BridgeModule::emergency_pause(Origin::signed(GUARDIAN)));
+            assert_eq!(BridgeModule::bridge_is_operational(), false);
+        })
+    }
+    #[test]
+    fn emergency_pause_by_user_should_fail() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(
+                BridgeModule::emergency_pause(Origin::signed(USER2)),
+                "Only validators or guardian can call this function"
+            );
+            assert_eq!(BridgeModule::bridge_is_operational(), true);
+        })
+    }
+    #[test]
+    fn emergency_pause_resumes_at_deadline() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(BridgeModule::emergency_pause(Origin::signed(V1)));
+
+            BridgeModule::on_initialize(PAUSE_DURATION - 1);
+            assert_eq!(BridgeModule::bridge_is_operational(), false);
+
+            BridgeModule::on_initialize(PAUSE_DURATION);
+            assert_eq!(BridgeModule::bridge_is_operational(), true);
+            assert_eq!(BridgeModule::pause_deadline(), None);
+        })
+    }
+    #[test]
+    fn extend_emergency_pause_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(
+                BridgeModule::extend_pause(Origin::signed(V2)),
+                "Bridge is not in emergency pause"
+            );
+            assert_ok!(BridgeModule::emergency_pause(Origin::signed(GUARDIAN)));
+            assert_noop!(
+                BridgeModule::extend_pause(Origin::signed(GUARDIAN)),
+                "Only validators can call this function"
+            );
+            System::set_block_number(PAUSE_DURATION - 1);
+            assert_ok!(BridgeModule::extend_pause(Origin::signed(V2)));
+            assert_eq!(BridgeModule::pause_deadline(), Some(2 * PAUSE_DURATION - 1));
+
+            BridgeModule::on_initialize(PAUSE_DURATION);
+            assert_eq!(BridgeModule::bridge_is_operational(), false);
+            BridgeModule::on_initialize(2 * PAUSE_DURATION - 1);
+            assert_eq!(BridgeModule::bridge_is_operational(), true);
+        })
+    }
+    #[test]
+    fn extend_emergency_pause_once_per_validator() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(BridgeModule::emergency_pause(Origin::signed(V1)));
+            assert_ok!(BridgeModule::extend_pause(Origin::signed(V2)));
+            assert_noop!(
+                BridgeModule::extend_pause(Origin::signed(V2)),
+                "This validator extended the pause already"
+            );
+
+            // extensions do not stack on the previous deadline
+            assert_ok!(BridgeModule::extend_pause(Origin::signed(V3)));
+            assert_eq!(BridgeModule::pause_deadline(), Some(PAUSE_DURATION));
+
+            // the next emergency pause can be extended again
+            BridgeModule::on_initialize(PAUSE_DURATION);
+            assert_ok!(BridgeModule::emergency_pause(Origin::signed(V1)));
+            assert_ok!(BridgeModule::extend_pause(Origin::signed(V2)));
+        })
+    }
+    #[test]
+    fn emergency_pause_resume_with_quorum_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(BridgeModule::emergency_pause(Origin::signed(V3)));
+            assert_ok!(BridgeModule::resume_bridge(Origin::signed(V1)));
+            assert_eq!(BridgeModule::bridge_is_operational(), false);
+            assert_ok!(BridgeModule::resume_bridge(Origin::signed(V2)));
+            assert_eq!(BridgeModule::bridge_is_operational(), true);
+            assert_eq!(BridgeModule::pause_deadline(), None);
+        })
+    }
+    #[test]
+    fn emergency_pause_made_permanent_by_quorum() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(BridgeModule::emergency_pause(Origin::signed(V3)));
+            assert_ok!(BridgeModule::pause_bridge(Origin::signed(V1)));
+            assert_ok!(BridgeModule::pause_bridge(Origin::signed(V2)));
+            assert_eq!(BridgeModule::pause_deadline(), None);
+
+            BridgeModule::on_initialize(PAUSE_DURATION);
+            assert_eq!(BridgeModule::bridge_is_operational(), false);
+        })
+    }
+    #[test]
+    fn pause_deposits_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            let eth_message_id = H256::from(ETH_MESSAGE_ID);
+            let eth_address = H160::from(ETH_ADDRESS);
+
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V1),
+                eth_message_id,
+                eth_address,
+                USER2,
+                1000
+            ));
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V2),
+                eth_message_id,
+                eth_address,
+                USER2,
+                1000
+            ));
+
+            assert_ok!(BridgeModule::pause_deposits(Origin::signed(V1)));
+            assert_eq!(BridgeModule::deposits_are_operational(), true);
+            assert_ok!(BridgeModule::pause_deposits(Origin::signed(V2)));
+            assert_eq!(BridgeModule::deposits_are_operational(), false);
+            assert_noop!(
+                BridgeModule::pause_deposits(Origin::signed(V3)),
+                "Deposits are paused already"
+            );
+
+            assert_noop!(
+                BridgeModule::multi_signed_mint(
+                    Origin::signed(V1),
+                    H256::from(&[1u8; 32]),
+                    eth_address,
+                    USER2,
+                    1000
+                ),
+                "Deposits are not operational"
+            );
+            // substrate -> ethereum keeps working
+            assert_ok!(BridgeModule::set_transfer(
+                Origin::signed(USER2),
+                eth_address,
+                500
+            ));
+            let sub_message_id = BridgeModule::message_id_by_transfer_id(2);
+            assert_ok!(BridgeModule::approve_transfer(
+                Origin::signed(V1),
+                sub_message_id
+            ));
+            assert_ok!(BridgeModule::approve_transfer(
+                Origin::signed(V2),
+                sub_message_id
+            ));
+            assert_eq!(TokenModule::locked(USER2), 500);
+        })
+    }
+    #[test]
+    fn pause_withdrawals_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            let eth_message_id = H256::from(ETH_MESSAGE_ID);
+            let eth_address = H160::from(ETH_ADDRESS);
+
+            assert_ok!(BridgeModule::pause_withdrawals(Origin::signed(V1)));
+            assert_ok!(BridgeModule::pause_withdrawals(Origin::signed(V3)));
+            assert_eq!(BridgeModule::withdrawals_are_operational(), false);
+
+            // ethereum -> substrate keeps working
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V1),
+                eth_message_id,
+                eth_address,
+                USER2,
+                1000
+            ));
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V2),
+                eth_message_id,
+                eth_address,
+                USER2,
+                1000
+            ));
+            assert_eq!(TokenModule::balance_of(USER2), 1000);
+
+            assert_noop!(
+                BridgeModule::set_transfer(Origin::signed(USER2), eth_address, 500),
+                "Withdrawals are not operational"
+            );
+
+            assert_ok!(BridgeModule::resume_withdrawals(Origin::signed(V1)));
+            assert_ok!(BridgeModule::resume_withdrawals(Origin::signed(V2)));
+            assert_eq!(BridgeModule::withdrawals_are_operational(), true);
+            assert_ok!(BridgeModule::set_transfer(
+                Origin::signed(USER2),
+                eth_address,
+                500
+            ));
+        })
+    }
+    #[test]
+    fn pause_token_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            let eth_message_id = H256::from(ETH_MESSAGE_ID);
+            let eth_address = H160::from(ETH_ADDRESS);
+            let token_id = TokenModule::token_default().id;
+
+            assert_ok!(BridgeModule::pause_token(Origin::signed(V1), token_id));
+            assert_ok!(BridgeModule::pause_token(Origin::signed(V2), token_id));
+            assert_eq!(BridgeModule::token_is_paused(token_id), true);
+            assert_noop!(
+                BridgeModule::multi_signed_mint(
+                    Origin::signed(V1),
+                    eth_message_id,
+                    eth_address,
+                    USER2,
+                    1000
+                ),
+                "Token is not operational"
+            );
+            assert_noop!(
+                BridgeModule::set_transfer(Origin::signed(USER2), eth_address, 500),
+                "Token is not operational"
+            );
+
+            assert_ok!(BridgeModule::resume_token(Origin::signed(V1), token_id));
+            assert_ok!(BridgeModule::resume_token(Origin::signed(V2), token_id));
+            assert_eq!(BridgeModule::token_is_paused(token_id), false);
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V1),
+                eth_message_id,
+                eth_address,
+                USER2,
+                1000
+            ));
+        })
+    }
+    #[test]
+    fn pause_and_resume_deposits_several_times_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            for _ in 0..3 {
+                assert_ok!(BridgeModule::pause_deposits(Origin::signed(V1)));
+                assert_ok!(BridgeModule::pause_deposits(Origin::signed(V2)));
+                assert_eq!(BridgeModule::deposits_are_operational(), false);
+                assert_ok!(BridgeModule::resume_deposits(Origin::signed(V2)));
+                assert_ok!(BridgeModule::resume_deposits(Origin::signed(V3)));
+                assert_eq!(BridgeModule::deposits_are_operational(), true);
+            }
+        })
+    }
+    #[test]
+    fn validators_management_works_while_bridge_paused() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(BridgeModule::emergency_pause(Origin::signed(V1)));
+
+            assert_ok!(BridgeModule::remove_validator(Origin::signed(V1), V3));
+            assert_ok!(BridgeModule::remove_validator(Origin::signed(V2), V3));
+            assert_eq!(BridgeModule::validators_count(), 2);
+
+            assert_ok!(BridgeModule::add_validator(Origin::signed(V1), V4));
+            assert_ok!(BridgeModule::add_validator(Origin::signed(V2), V4));
+            assert_eq!(BridgeModule::validators_count(), 3);
+        })
+    }
+    #[test]
+    fn force_add_and_remove_validator_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(
+                BridgeModule::force_add_validator(Origin::signed(V1), V4),
+                "bad origin: expected to be a root origin"
+            );
+            assert_ok!(BridgeModule::force_add_validator(Origin::ROOT, V4));
+            assert_eq!(BridgeModule::validators_count(), 4);
+            assert_eq!(BridgeModule::validators(V4), true);
+            assert_noop!(
+                BridgeModule::force_add_validator(Origin::ROOT, V4),
+                "Account is a validator already"
+            );
+
+            assert_ok!(BridgeModule::force_remove_validator(Origin::ROOT, V1));
+            assert_eq!(BridgeModule::validators_count(), 3);
+            assert_noop!(
+                BridgeModule::approve_transfer(Origin::signed(V1), H256::from(ETH_MESSAGE_ID)),
+                "Only validators can call this function"
+            );
+        })
+    }
+    #[test]
+    fn force_remove_last_validator_should_fail() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(BridgeModule::force_remove_validator(Origin::ROOT, V1));
+            assert_ok!(BridgeModule::force_remove_validator(Origin::ROOT, V2));
+            assert_noop!(
+                BridgeModule::force_remove_validator(Origin::ROOT, V3),
+                "Can not remove last validator."
+            );
+        })
+    }
+    #[test]
+    fn force_pause_and_resume_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(
+                BridgeModule::force_pause(Origin::signed(V1)),
+                "bad origin: expected to be a root origin"
+            );
+            assert_ok!(BridgeModule::emergency_pause(Origin::signed(V1)));
+            assert_ok!(BridgeModule::force_pause(Origin::ROOT));
+            assert_eq!(BridgeModule::bridge_is_operational(), false);
+            assert_eq!(BridgeModule::pause_deadline(), None);
+
+            assert_ok!(BridgeModule::force_resume(Origin::ROOT));
+            assert_eq!(BridgeModule::bridge_is_operational(), true);
+        })
+    }
+    #[test]
+    fn force_cancel_transfer_should_unlock_funds() {
+        with_externalities(&mut new_test_ext(), || {
+            let eth_message_id = H256::from(ETH_MESSAGE_ID);
+            let eth_address = H160::from(ETH_ADDRESS);
+
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V1),
+                eth_message_id,
+                eth_address,
+                USER2,
+                1000
+            ));
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V2),
+                eth_message_id,
+                eth_address,
+                USER2,
+                1000
+            ));
+            assert_ok!(BridgeModule::set_transfer(
+                Origin::signed(USER2),
+                eth_address,
+                500
+            ));
+            let sub_message_id = BridgeModule::message_id_by_transfer_id(1);
+            assert_ok!(BridgeModule::approve_transfer(
+                Origin::signed(V1),
+                sub_message_id
+            ));
+            assert_ok!(BridgeModule::approve_transfer(
+                Origin::signed(V2),
+                sub_message_id
+            ));
+            assert_eq!(TokenModule::locked(USER2), 500);
+            assert_eq!(Balances::reserved_balance(USER2), DEPOSIT);
+
+            assert_noop!(
+                BridgeModule::force_cancel_transfer(Origin::ROOT, eth_message_id),
+                "Only withdrawals can be canceled"
+            );
+            assert_ok!(BridgeModule::force_cancel_transfer(
+                Origin::ROOT,
+                sub_message_id
+            ));
+            assert_eq!(TokenModule::locked(USER2), 0);
+            assert_eq!(TokenModule::balance_of(USER2), 1000);
+            assert_eq!(Balances::reserved_balance(USER2), 0);
+            assert_eq!(Balances::free_balance(USER2), 300000);
+            assert_eq!(
+                BridgeModule::messages(sub_message_id).status,
+                Status::Canceled
+            );
+            assert_eq!(BridgeModule::transfers(1).open, false);
+
+            assert_noop!(
+                BridgeModule::force_cancel_transfer(Origin::ROOT, sub_message_id),
+                "Transfer is finished already"
+            );
+        })
+    }
+    #[test]
+    fn force_cancel_pending_transfer_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            let eth_address = H160::from(ETH_ADDRESS);
+
+            assert_ok!(BridgeModule::set_transfer(
+                Origin::signed(USER2),
+                eth_address,
+                500
+            ));
+            let sub_message_id = BridgeModule::message_id_by_transfer_id(0);
+            assert_ok!(BridgeModule::approve_transfer(
+                Origin::signed(V1),
+                sub_message_id
+            ));
+
+            assert_ok!(BridgeModule::force_cancel_transfer(
+                Origin::ROOT,
+                sub_message_id
+            ));
+            assert_eq!(
+                BridgeModule::messages(sub_message_id).status,
+                Status::Canceled
+            );
+            assert_noop!(
+                BridgeModule::approve_transfer(Origin::signed(V2), sub_message_id),
+                "This transfer is not open"
+            );
+        })
+    }
+    #[test]
+    fn migration_to_v1_should_decode_old_transfer_messages() {
+        with_externalities(&mut new_test_ext(), || {
+            let message_id = H256::from(ETH_MESSAGE_ID);
+            let old = v0::TransferMessage {
+                message_id,
+                eth_address: H160::from(ETH_ADDRESS),
+                substrate_address: USER2,
+                amount: 1000,
+                status: Status::Approved,
+                action: Status::Withdraw,
+            };
+            assert_ok!(BridgeModule::create_transfer(message_id, Kind::Transfer));
+            storage::put(&<TransferMessages<Test>>::key_for(message_id), &old);
+
+            // old encoding can not be read with the new layout
+            assert_eq!(BridgeModule::storage_version(), 0);
+            assert_eq!(BridgeModule::messages(message_id).amount, 0);
+
+            BridgeModule::on_initialize(1);
+
+            assert_eq!(BridgeModule::storage_version(), STORAGE_VERSION);
+            let message = BridgeModule::messages(message_id);
+            assert_eq!(message.message_id, message_id);
+            assert_eq!(message.eth_address, H160::from(ETH_ADDRESS));
+            assert_eq!(message.substrate_address, USER2);
+            assert_eq!(message.amount, 1000);
+            assert_eq!(message.status, Status::Approved);
+            assert_eq!(message.action, Status::Withdraw);
+            assert_eq!(message.token_id, TokenModule::token_default().id);
+        })
+    }
+    #[test]
+    fn migration_should_run_once() {
+        with_externalities(&mut new_test_ext(), || {
+            BridgeModule::on_initialize(1);
+            assert_eq!(BridgeModule::storage_version(), STORAGE_VERSION);
+
+            // entries written after the upgrade are left as is
+            let eth_address = H160::from(ETH_ADDRESS);
+            assert_ok!(BridgeModule::set_transfer(
+                Origin::signed(USER2),
+                eth_address,
+                500
+            ));
+            let sub_message_id = BridgeModule::message_id_by_transfer_id(0);
+            BridgeModule::on_initialize(2);
+
+            let message = BridgeModule::messages(sub_message_id);
+            assert_eq!(message.amount, 500);
+            assert_eq!(message.status, Status::Withdraw);
+        })
+    }
+    #[test]
+    fn migration_should_be_split_in_batches() {
+        with_externalities(&mut new_test_ext(), || {
+            let old_messages = MIGRATION_BATCH + 1;
+            for i in 0..old_messages {
+                let message_id = BlakeTwo256::hash_of(&i);
+                let old = v0::TransferMessage {
+                    message_id,
+                    eth_address: H160::from(ETH_ADDRESS),
+                    substrate_address: USER2,
+                    amount: 1000,
+                    status: Status::Withdraw,
+                    action: Status::Withdraw,
+                };
+                assert_ok!(BridgeModule::create_transfer(message_id, Kind::Transfer));
+                storage::put(&<TransferMessages<Test>>::key_for(message_id), &old);
+            }
+            let last = BridgeModule::message_id_by_transfer_id(MIGRATION_BATCH);
+
+            BridgeModule::on_initialize(1);
+            assert_eq!(BridgeModule::storage_version(), 0);
+            assert_eq!(
+                BridgeModule::migration_progress(),
+                Some((MIGRATION_BATCH, old_messages))
+            );
+            assert_eq!(BridgeModule::messages(last).amount, 0);
+            assert_noop!(
+                BridgeModule::pause_bridge(Origin::signed(V1)),
+                "Bridge storage is being migrated"
+            );
+
+            // transfers created during the migration are not part of it
+            assert_ok!(BridgeModule::set_transfer(
+                Origin::signed(USER2),
+                H160::from(ETH_ADDRESS),
+                500
+            ));
+            let sub_message_id = BridgeModule::message_id_by_transfer_id(old_messages);
+
+            BridgeModule::on_initialize(2);
+            assert_eq!(BridgeModule::storage_version(), STORAGE_VERSION);
+            assert_eq!(BridgeModule::migration_progress(), None);
+            assert_eq!(BridgeModule::messages(last).amount, 1000);
+            assert_eq!(BridgeModule::messages(sub_message_id).amount, 500);
+            assert_ok!(BridgeModule::pause_bridge(Origin::signed(V1)));
+        })
+    }
+    #[test]
+    fn set_fee_rate_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(BridgeModule::set_fee_rate(Origin::signed(V1), 10));
+            assert_ok!(BridgeModule::set_fee_rate(Origin::signed(V2), 20));
+            assert_eq!(TokenModule::fee_exchange_rate(), 0);
+
+            assert_ok!(BridgeModule::set_fee_rate(Origin::signed(V3), 10));
+            assert_eq!(TokenModule::fee_exchange_rate(), 10);
+
+            // the next round is voted from scratch
+            assert_ok!(BridgeModule::set_fee_rate(Origin::signed(V3), 20));
+            assert_eq!(TokenModule::fee_exchange_rate(), 10);
+            assert_ok!(BridgeModule::set_fee_rate(Origin::signed(V1), 20));
+            assert_eq!(TokenModule::fee_exchange_rate(), 20);
+            assert_noop!(
+                BridgeModule::set_fee_rate(Origin::signed(USER2), 0),
+                "Only validators can call this function"
+            );
+        })
+    }
+    #[test]
+    fn set_transfer_deposit_is_refunded_after_burn() {
+        with_externalities(&mut new_test_ext(), || {
+            let eth_message_id = H256::from(ETH_MESSAGE_ID);
+            let eth_address = H160::from(ETH_ADDRESS);
+
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V1),
+                eth_message_id,
+                eth_address,
+                USER2,
+                1000
+            ));
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V2),
+                eth_message_id,
+                eth_address,
+                USER2,
+                1000
+            ));
+            assert_ok!(BridgeModule::set_transfer(
+                Origin::signed(USER2),
+                eth_address,
+                500
+            ));
+            let sub_message_id = BridgeModule::message_id_by_transfer_id(1);
+            assert_eq!(Balances::reserved_balance(USER2), DEPOSIT);
+            assert_eq!(BridgeModule::transfer_deposit_of(sub_message_id), DEPOSIT);
+
+            assert_ok!(BridgeModule::approve_transfer(
+                Origin::signed(V1),
+                sub_message_id
+            ));
+            assert_ok!(BridgeModule::approve_transfer(
+                Origin::signed(V2),
+                sub_message_id
+            ));
+            assert_ok!(BridgeModule::confirm_transfer(
+                Origin::signed(V1),
+                sub_message_id
+            ));
+            assert_eq!(Balances::reserved_balance(USER2), DEPOSIT);
+            assert_ok!(BridgeModule::confirm_transfer(
+                Origin::signed(V2),
+                sub_message_id
+            ));
+
+            assert_eq!(TokenModule::balance_of(USER2), 500);
+            assert_eq!(Balances::reserved_balance(USER2), 0);
+            assert_eq!(Balances::free_balance(USER2), 300000);
+            assert_eq!(BridgeModule::transfer_deposit_of(sub_message_id), 0);
+        })
+    }
+    #[test]
+    fn set_transfer_requires_deposit() {
+        with_externalities(&mut new_test_ext(), || {
+            let eth_address = H160::from(ETH_ADDRESS);
+            // the account has no native currency to reserve
+            assert!(
+                BridgeModule::set_transfer(Origin::signed(GUARDIAN), eth_address, 500).is_err()
+            );
+            assert_eq!(BridgeModule::bridge_transfers_count(), 0);
+        })
+    }
+    #[test]
+    fn set_transfer_pays_weight_fee() {
+        with_externalities(&mut new_test_ext(), || {
+            let eth_address = H160::from(ETH_ADDRESS);
+            assert_ok!(TokenModule::set_weight_fee(Origin::ROOT, 3));
+
+            assert_ok!(BridgeModule::set_transfer(
+                Origin::signed(USER2),
+                eth_address,
+                500
+            ));
+            let fee = 3 * weights::SET_TRANSFER.units() as u128;
+            assert_eq!(Balances::free_balance(USER2), 300000 - fee - DEPOSIT);
+        })
+    }
+
+    #[test]
+    fn duplicate_set_transfer_should_fail() {
+        with_externalities(&mut new_test_ext(), || {
+            let eth_address = H160::from(ETH_ADDRESS);
+            assert_ok!(BridgeModule::set_transfer(
+                Origin::signed(USER2),
+                eth_address,
+                500
+            ));
+            assert_noop!(
+                BridgeModule::set_transfer(Origin::signed(USER2), eth_address, 500),
+                "This transfer already open"
+            );
+            assert_eq!(Balances::reserved_balance(USER2), DEPOSIT);
+        })
+    }
+    #[test]
+    fn validator_calls_pay_weight_fee() {
+        with_externalities(&mut new_test_ext(), || {
+            let eth_message_id = H256::from(ETH_MESSAGE_ID);
+            let eth_address = H160::from(ETH_ADDRESS);
+            assert_ok!(TokenModule::set_weight_fee(Origin::ROOT, 3));
+
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V1),
+                eth_message_id,
+                eth_address,
+                USER2,
+                1000
+            ));
+            let fee = 3 * weights::MULTI_SIGNED_MINT.units() as u128;
+            assert_eq!(Balances::free_balance(V1), 100000 - fee);
+
+            assert_ok!(BridgeModule::pause_bridge(Origin::signed(V2)));
+            let fee = 3 * weights::VOTE.units() as u128;
+            assert_eq!(Balances::free_balance(V2), 100000 - fee);
+
+            // forced calls are not signed, there is no account to charge
+            assert_ok!(BridgeModule::force_pause(Origin::ROOT));
+        })
+    }
+
+    // harness for the weights module, prints the time of the calls per weight unit
+    #[test]
+    #[ignore]
+    fn measure_dispatch_weights() {
+        use std::time::Instant;
+        const RUNS: u64 = 1000;
+
+        fn report(name: &str, weight: weights::Weight, started: Instant) {
+            let elapsed = started.elapsed();
+            let nanos = (elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64) / RUNS;
+            println!(
+                "{}: {:?}, {} ns per call, {} ns per unit",
+                name,
+                weight,
+                nanos,
+                nanos / weight.units()
+            );
+        }
+
+        with_externalities(&mut new_test_ext(), || {
+            let eth_address = H160::from(ETH_ADDRESS);
+            assert_ok!(TokenModule::_mint(USER1, RUNS * 1000));
+            assert_ok!(TokenModule::_mint(USER2, RUNS * 1000));
+
+            let started = Instant::now();
+            for _ in 0..RUNS {
+                assert_ok!(TokenModule::transfer(Origin::signed(USER1), USER2, 1));
+            }
+            report("transfer", weights::TRANSFER, started);
+
+            let started = Instant::now();
+            for i in 0..RUNS {
+                assert_ok!(TokenModule::approve(Origin::signed(USER1), USER2, i));
+            }
+            report("approve", weights::APPROVE, started);
+
+            assert_ok!(TokenModule::approve(Origin::signed(USER1), USER2, RUNS));
+            let started = Instant::now();
+            for _ in 0..RUNS {
+                assert_ok!(TokenModule::transfer_from(
+                    Origin::signed(USER2),
+                    USER1,
+                    V1,
+                    1
+                ));
+            }
+            report("transfer_from", weights::TRANSFER_FROM, started);
+
+            let started = Instant::now();
+            for i in 0..RUNS {
+                assert_ok!(BridgeModule::set_transfer(
+                    Origin::signed(USER2),
+                    eth_address,
+                    i + 1
+                ));
+            }
+            report("set_transfer", weights::SET_TRANSFER, started);
+
+            let started = Instant::now();
+            for i in 0..RUNS {
+                let message_id = BridgeModule::message_id_by_transfer_id(i);
+                assert_ok!(BridgeModule::approve_transfer(
+                    Origin::signed(V1),
+                    message_id
+                ));
+            }
+            report("approve_transfer", weights::APPROVE_TRANSFER, started);
+
+            for i in 0..RUNS {
+                let message_id = BridgeModule::message_id_by_transfer_id(i);
+                assert_ok!(BridgeModule::approve_transfer(
+                    Origin::signed(V2),
+                    message_id
+                ));
+            }
+            let started = Instant::now();
+            for i in 0..RUNS {
+                let message_id = BridgeModule::message_id_by_transfer_id(i);
+                assert_ok!(BridgeModule::confirm_transfer(
+                    Origin::signed(V1),
+                    message_id
+                ));
+            }
+            report("confirm_transfer", weights::CONFIRM_TRANSFER, started);
+
+            let started = Instant::now();
+            for i in 0..RUNS {
+                let message_id = BlakeTwo256::hash_of(&i);
+                assert_ok!(BridgeModule::multi_signed_mint(
+                    Origin::signed(V1),
+                    message_id,
+                    eth_address,
+                    USER2,
+                    1
+                ));
+            }
+            report("multi_signed_mint", weights::MULTI_SIGNED_MINT, started);
+        })
+    }
+    #[test]
+    fn check_vote_should_work() {
+        with_externalities(&mut new_test_ext(), || {
+            let eth_message_id = H256::from(ETH_MESSAGE_ID);
+            let eth_address = H160::from(ETH_ADDRESS);
+            let mint = Call::multi_signed_mint(eth_message_id, eth_address, USER2, 1000);
+
+            assert_eq!(
+                BridgeModule::check_vote(&USER1, &mint),
+                Err("Only validators can call this function")
+            );
+            let tag = BridgeModule::check_vote(&V1, &mint).unwrap().unwrap();
+            assert_ne!(Some(tag), BridgeModule::check_vote(&V2, &mint).unwrap());
+            assert_eq!(
+                BridgeModule::check_vote(&V1, &Call::pause_bridge()),
+                Ok(None)
+            );
+
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V1),
+                eth_message_id,
+                eth_address,
+                USER2,
+                1000
+            ));
+            assert!(BridgeModule::check_vote(&V2, &mint).is_ok());
+            assert_ok!(BridgeModule::multi_signed_mint(
+                Origin::signed(V2),
+                eth_message_id,
+                eth_address,
+                USER2,
+                1000
+            ));
+            assert_eq!(
+                BridgeModule::check_vote(&V3, &mint),
+                Err("This transfer is not open")
+            );
+
+            assert_ok!(BridgeModule::set_transfer(
+                Origin::signed(USER2),
+                eth_address,
+                500
+            ));
+            let sub_message_id = BridgeModule::message_id_by_transfer_id(1);
+            let approve = Call::approve_transfer(sub_message_id);
+            let confirm = Call::confirm_transfer(sub_message_id);
+            assert!(BridgeModule::check_vote(&V1, &approve).is_ok());
+            assert_eq!(
+                BridgeModule::check_vote(&V1, &confirm),
+                Err("This transfer must be approved first.")
+            );
+
+            assert_ok!(BridgeModule::approve_transfer(
+                Origin::signed(V1),
+                sub_message_id
+            ));
+            assert_ok!(BridgeModule::approve_transfer(
+                Origin::signed(V2),
+                sub_message_id
+            ));
+            assert_eq!(
+                BridgeModule::check_vote(&V3, &approve),
+                Err("This transfer is not open")
+            );
+            assert!(BridgeModule::check_vote(&V1, &confirm).is_ok());
+
+            assert_ok!(BridgeModule::confirm_transfer(
+                Origin::signed(V1),
+                sub_message_id
+            ));
+            assert!(BridgeModule::check_vote(&V2, &confirm).is_ok());
+            assert_ok!(BridgeModule::confirm_transfer(
+                Origin::signed(V2),
+                sub_message_id
+            ));
+            assert_eq!(
+                BridgeModule::check_vote(&V3, &confirm),
+                Err("This transfer is not open")
+            );
+        })
+    }
 }
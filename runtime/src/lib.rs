@@ -15,8 +15,11 @@ use primitives::{ed25519, sr25519, OpaqueMetadata};
 use rstd::prelude::*;
 use runtime_primitives::{
     self, create_runtime_str, generic,
-    traits::{BlakeTwo256, Block as BlockT, Convert, DigestFor, NumberFor, StaticLookup, Verify},
-    transaction_validity::TransactionValidity,
+    traits::{
+        BlakeTwo256, Block as BlockT, Convert, DigestFor, EnsureOrigin, NumberFor, StaticLookup,
+        Verify,
+    },
+    transaction_validity::{TransactionPriority, TransactionValidity},
     ApplyResult,
 };
 #[cfg(feature = "std")]
@@ -61,7 +64,8 @@ pub type Nonce = u64;
 
 pub mod bridge;
 mod token;
-mod types;
+pub mod types;
+pub mod weights;
 
 /// Opaque types. These are used by the CLI to instantiate machinery that don't need to know
 /// the specifics of the runtime. They can then be made to be agnostic over specific formats
@@ -92,7 +96,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_name: create_runtime_str!("erc20-substrate-bridge"),
     impl_name: create_runtime_str!("erc20-substrate-bridge"),
     authoring_version: 3,
-    spec_version: 4,
+    spec_version: 5,
     impl_version: 4,
     apis: RUNTIME_API_VERSIONS,
 };
@@ -274,10 +278,28 @@ impl contract::Trait for Runtime {
 
 impl token::Trait for Runtime {
     type Event = Event;
+    type Signature = AccountSignature;
+}
+
+/// Origin of bridge emergency operations: sudo or a council motion approved by at least two members,
+/// the same threshold as the treasury `RejectOrigin`.
+pub struct EnsureRootOrCouncil;
+
+impl EnsureOrigin<Origin> for EnsureRootOrCouncil {
+    type Success = ();
+
+    fn ensure_origin(o: Origin) -> Result<Self::Success, &'static str> {
+        system::ensure_root(o.clone()).or_else(|_| {
+            council_motions::EnsureMembers::<_2>::ensure_origin(o)
+                .map(|_| ())
+                .map_err(|_| "bad origin: expected to be root or council motion")
+        })
+    }
 }
 
 impl bridge::Trait for Runtime {
     type Event = Event;
+    type ForceOrigin = EnsureRootOrCouncil;
 }
 
 construct_runtime!(
@@ -324,7 +346,56 @@ pub type UncheckedExtrinsic =
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Nonce, Call>;
 /// Executive: handles dispatch to the various modules.
-pub type Executive = executive::Executive<Runtime, Block, Context, Balances, AllModules>;
+/// Transaction fees are paid in native currency or in bridged tokens, see `token::Module`.
+pub type Executive = executive::Executive<Runtime, Block, Context, Token, AllModules>;
+
+/// Code of the bridge votes rejected by the transaction pool.
+const INVALID_BRIDGE_VOTE: i8 = -64;
+
+/// Validates transactions for the pool. Bridge votes of non-validators and votes for closed
+/// proposals are dropped here, the valid ones are included before other transactions.
+fn validate_transaction(tx: UncheckedExtrinsic) -> TransactionValidity {
+    let vote = match (&tx.signature, &tx.function) {
+        (Some((address, _, _, _)), Call::Bridge(call)) => Some((address.clone(), call.clone())),
+        _ => None,
+    };
+    let validity = Executive::validate_transaction(tx);
+    let (address, call) = match vote {
+        Some(vote) => vote,
+        None => return validity,
+    };
+
+    match validity {
+        TransactionValidity::Valid {
+            priority,
+            requires,
+            mut provides,
+            longevity,
+        } => {
+            let tag = Indices::lookup(address).and_then(|who| Bridge::check_vote(&who, &call));
+            match tag {
+                Ok(Some(tag)) => {
+                    provides.push(tag);
+                    TransactionValidity::Valid {
+                        priority: priority
+                            .saturating_add(bridge::VOTE_PRIORITY as TransactionPriority),
+                        requires,
+                        provides,
+                        longevity,
+                    }
+                }
+                Ok(None) => TransactionValidity::Valid {
+                    priority,
+                    requires,
+                    provides,
+                    longevity,
+                },
+                Err(_) => TransactionValidity::Invalid(INVALID_BRIDGE_VOTE),
+            }
+        }
+        invalid => invalid,
+    }
+}
 
 // Implement our runtime API endpoints. This is just a bunch of proxying.
 impl_runtime_apis! {
@@ -376,7 +447,7 @@ impl_runtime_apis! {
 
     impl runtime_api::TaggedTransactionQueue<Block> for Runtime {
         fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity {
-            Executive::validate_transaction(tx)
+            validate_transaction(tx)
         }
     }
 
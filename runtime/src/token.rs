@@ -3,13 +3,27 @@
 /// and transfer tokens on substrate side freely or operate with total_supply
 ///
 use crate::types::{TokenBalance, TokenId};
+use crate::weights::{self, Weight};
 use parity_codec::{Decode, Encode};
 use rstd::prelude::Vec;
-use runtime_primitives::traits::{StaticLookup, Zero};
+use runtime_primitives::traits::{
+    As, CheckedAdd, CheckedMul, CheckedSub, MakePayment, Member, StaticLookup, Verify, Zero,
+};
 use support::{
-    decl_event, decl_module, decl_storage, dispatch::Result, ensure, StorageMap, StorageValue,
+    decl_event, decl_module, decl_storage,
+    dispatch::Result,
+    ensure,
+    traits::{Currency, ExistenceRequirement, OnUnbalanced, WithdrawReason},
+    Parameter, StorageMap, StorageValue,
 };
-use system::{self, ensure_signed};
+use system::{self, ensure_root, ensure_signed};
+
+/// current layout of the module storage, see `migrate`
+const STORAGE_VERSION: u32 = 1;
+/// limits the work done by a single batch_transfer call
+const MAX_BATCH_TRANSFERS: usize = 500;
+/// domain separator of the signed permit payload
+const PERMIT_PREFIX: &[u8] = b"token:permit";
 
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -17,6 +31,7 @@ pub struct Token {
     pub id: TokenId,
     pub decimals: u16,
     pub symbol: Vec<u8>,
+    pub name: Vec<u8>,
 }
 
 decl_event!(
@@ -28,22 +43,42 @@ decl_event!(
         Approval(AccountId, AccountId, TokenBalance),
         Mint(AccountId, TokenBalance),
         Burn(AccountId, TokenBalance),
+        AccountReaped(AccountId, TokenBalance),
+        FeePaid(AccountId, TokenBalance),
     }
 );
 
 pub trait Trait: balances::Trait + system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// signature of the off-chain permit, made by the owner of the tokens
+    type Signature: Parameter + Member + Verify<Signer = Self::AccountId>;
 }
 
 decl_storage! {
     trait Store for Module<T: Trait> as TokenStorage {
+        StorageVersion get(storage_version): u32;
         Count get(count): TokenId;
         Locked get(locked): map(T::AccountId) => TokenBalance;
 
-        TokenDefault get(token_default): Token = Token{id: 0, decimals: 18, symbol: Vec::from("TOKEN")};
+        TokenDefault get(token_default): Token = Token{id: 0, decimals: 18, symbol: Vec::from("TOKEN"), name: Vec::from("TOKEN")};
         TotalSupply get(total_supply): TokenBalance;
         Balance get(balance_of): map (T::AccountId) => TokenBalance;
         Allowance get(allowance_of): map (T::AccountId, T::AccountId) => TokenBalance;
+
+        // accounts with less tokens are reaped, their dust goes to the collector or is burned
+        MinimumBalance get(minimum_balance): TokenBalance;
+        DustCollector get(dust_collector): Option<T::AccountId>;
+
+        // tokens charged per unit of the native transaction fee, zero disables paying fees in tokens
+        FeeExchangeRate get(fee_exchange_rate): TokenBalance;
+        // fees paid in tokens are burned if there is no collector
+        FeeCollector get(fee_collector): Option<T::AccountId>;
+
+        // native fee per unit of the call weight, zero disables weight fees
+        WeightFee get(weight_fee): T::Balance;
+
+        // number of permits used by the owner, protects signed permits from replay
+        PermitNonce get(permit_nonce): map (T::AccountId) => u64;
     }
 }
 
@@ -51,11 +86,16 @@ decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         fn deposit_event<T>() = default;
 
+        fn on_initialize(_n: T::BlockNumber) {
+            Self::migrate();
+        }
+
         fn transfer(origin,
             to: <T::Lookup as StaticLookup>::Source,
             #[compact] amount: TokenBalance
         ) -> Result{
             let sender = ensure_signed(origin)?;
+            Self::charge_weight(&sender, weights::TRANSFER)?;
             let to = T::Lookup::lookup(to)?;
             ensure!(!amount.is_zero(), "transfer amount should be non-zero");
 
@@ -63,32 +103,145 @@ decl_module! {
             Ok(())
         }
 
+        // all-or-nothing: either every transfer of the batch is made or none of them
+        fn batch_transfer(origin,
+            transfers: Vec<(<T::Lookup as StaticLookup>::Source, TokenBalance)>
+        ) -> Result{
+            let sender = ensure_signed(origin)?;
+            ensure!(!transfers.is_empty(), "batch should be non-empty");
+            ensure!(transfers.len() <= MAX_BATCH_TRANSFERS, "too many transfers in batch");
+            Self::charge_weight(&sender, weights::TRANSFER.times(transfers.len() as u64))?;
+
+            let mut resolved = Vec::with_capacity(transfers.len());
+            for (to, amount) in transfers {
+                let to = T::Lookup::lookup(to)?;
+                ensure!(!amount.is_zero(), "transfer amount should be non-zero");
+                resolved.push((to, amount));
+            }
+
+            Self::make_batch_transfer(sender, resolved)
+        }
+
         fn approve(origin,
             spender: <T::Lookup as StaticLookup>::Source,
             #[compact] value: TokenBalance
         ) -> Result{
             let sender = ensure_signed(origin)?;
+            Self::charge_weight(&sender, weights::APPROVE)?;
             let spender = T::Lookup::lookup(spender)?;
 
-            <Allowance<T>>::insert((sender.clone(), spender.clone()), value);
+            Self::set_allowance(&sender, &spender, value);
 
             Self::deposit_event(RawEvent::Approval(sender, spender, value));
             Ok(())
         }
 
+        fn increase_allowance(origin,
+            spender: <T::Lookup as StaticLookup>::Source,
+            #[compact] added_value: TokenBalance
+        ) -> Result{
+            let sender = ensure_signed(origin)?;
+            Self::charge_weight(&sender, weights::CHANGE_ALLOWANCE)?;
+            let spender = T::Lookup::lookup(spender)?;
+
+            let allowance = Self::allowance_of((sender.clone(), spender.clone()));
+            let updated_allowance = allowance.checked_add(added_value).ok_or("overflow in calculating allowance")?;
+            Self::set_allowance(&sender, &spender, updated_allowance);
+
+            Self::deposit_event(RawEvent::Approval(sender, spender, updated_allowance));
+            Ok(())
+        }
+
+        fn decrease_allowance(origin,
+            spender: <T::Lookup as StaticLookup>::Source,
+            #[compact] subtracted_value: TokenBalance
+        ) -> Result{
+            let sender = ensure_signed(origin)?;
+            Self::charge_weight(&sender, weights::CHANGE_ALLOWANCE)?;
+            let spender = T::Lookup::lookup(spender)?;
+
+            let allowance = Self::allowance_of((sender.clone(), spender.clone()));
+            let updated_allowance = allowance.checked_sub(subtracted_value).ok_or("underflow in calculating allowance")?;
+            Self::set_allowance(&sender, &spender, updated_allowance);
+
+            Self::deposit_event(RawEvent::Approval(sender, spender, updated_allowance));
+            Ok(())
+        }
+
         fn transfer_from(origin,
-            from: T::AccountId,
-            to: T::AccountId,
+            from: <T::Lookup as StaticLookup>::Source,
+            to: <T::Lookup as StaticLookup>::Source,
             #[compact] value: TokenBalance
         ) -> Result{
             let sender = ensure_signed(origin)?;
+            Self::charge_weight(&sender, weights::TRANSFER_FROM)?;
+            let from = T::Lookup::lookup(from)?;
+            let to = T::Lookup::lookup(to)?;
             let allowance = Self::allowance_of((from.clone(), sender.clone()));
 
             let updated_allowance = allowance.checked_sub(value).ok_or("underflow in calculating allowance")?;
 
             Self::make_transfer(from.clone(), to.clone(), value)?;
 
-            <Allowance<T>>::insert((from, sender), updated_allowance);
+            Self::set_allowance(&from, &sender, updated_allowance);
+            Self::deposit_event(RawEvent::Approval(from, sender, updated_allowance));
+            Ok(())
+        }
+
+        // approval signed off-chain by the owner, any account can submit it
+        fn permit(origin,
+            owner: <T::Lookup as StaticLookup>::Source,
+            spender: <T::Lookup as StaticLookup>::Source,
+            #[compact] value: TokenBalance,
+            deadline: T::BlockNumber,
+            signature: T::Signature
+        ) -> Result{
+            let relayer = ensure_signed(origin)?;
+            Self::charge_weight(&relayer, weights::PERMIT)?;
+            let owner = T::Lookup::lookup(owner)?;
+            let spender = T::Lookup::lookup(spender)?;
+            ensure!(<system::Module<T>>::block_number() <= deadline, "permit is expired");
+
+            let nonce = Self::permit_nonce(&owner);
+            let payload = (PERMIT_PREFIX, &owner, &spender, value, nonce, deadline).encode();
+            ensure!(signature.verify(&payload[..], &owner), "invalid permit signature");
+
+            let next_nonce = nonce.checked_add(1).ok_or("overflow in permit nonce")?;
+            <PermitNonce<T>>::insert(owner.clone(), next_nonce);
+            Self::set_allowance(&owner, &spender, value);
+
+            Self::deposit_event(RawEvent::Approval(owner, spender, value));
+            Ok(())
+        }
+
+        fn set_weight_fee(origin, #[compact] fee: T::Balance) -> Result {
+            ensure_root(origin)?;
+            <WeightFee<T>>::put(fee);
+            Ok(())
+        }
+
+        fn set_fee_collector(origin, collector: Option<T::AccountId>) -> Result {
+            ensure_root(origin)?;
+            match collector {
+                Some(collector) => <FeeCollector<T>>::put(collector),
+                None => <FeeCollector<T>>::kill(),
+            }
+            Ok(())
+        }
+
+        fn set_minimum_balance(origin, #[compact] value: TokenBalance) -> Result {
+            ensure_root(origin)?;
+            <MinimumBalance<T>>::put(value);
+            Ok(())
+        }
+
+        // dust of reaped accounts is burned if there is no collector
+        fn set_dust_collector(origin, collector: Option<T::AccountId>) -> Result {
+            ensure_root(origin)?;
+            match collector {
+                Some(collector) => <DustCollector<T>>::put(collector),
+                None => <DustCollector<T>>::kill(),
+            }
             Ok(())
         }
 
@@ -96,20 +249,32 @@ decl_module! {
 }
 
 impl<T: Trait> Module<T> {
+    /// bring the storage written by previous runtime versions to the current layout
+    fn migrate() {
+        let version = Self::storage_version();
+        if version >= STORAGE_VERSION {
+            return;
+        }
+
+        // v1 is the first versioned layout, v0 entries are already compatible with it
+        <StorageVersion<T>>::put(STORAGE_VERSION);
+    }
+
     pub fn _burn(from: T::AccountId, amount: TokenBalance) -> Result {
         ensure!(
             Self::total_supply() >= amount,
             "Cannot burn more than total supply"
         );
 
-        let free_balance = <Balance<T>>::get(&from) - <Locked<T>>::get(&from);
+        let balance = <Balance<T>>::get(&from);
+        let free_balance = Self::free_balance(&from)?;
         ensure!(
             free_balance > TokenBalance::zero(),
             "Cannot burn with zero balance"
         );
         ensure!(free_balance >= amount, "not enough because of locked funds");
 
-        let next_balance = free_balance
+        let next_balance = balance
             .checked_sub(amount)
             .ok_or("underflow subtracting from balance burn")?;
         let next_total = Self::total_supply()
@@ -119,9 +284,220 @@ impl<T: Trait> Module<T> {
         <Balance<T>>::insert(from.clone(), next_balance);
         <TotalSupply<T>>::put(next_total);
 
+        Self::deposit_event(RawEvent::Burn(from.clone(), amount));
+        Self::reap_if_dust(&from)
+    }
+
+    /// exchange rate is set by bridge validators
+    pub fn set_fee_exchange_rate(rate: TokenBalance) {
+        <FeeExchangeRate<T>>::put(rate);
+    }
+
+    /// charge the caller for the storage accesses of the call
+    pub fn charge_weight(who: &T::AccountId, weight: Weight) -> Result {
+        let fee = Self::weight_fee()
+            .checked_mul(&T::Balance::sa(weight.units()))
+            .ok_or("overflow calculating weight fee")?;
+        if fee.is_zero() {
+            return Ok(());
+        }
+
+        Self::pay_fee(who, fee)
+    }
+
+    /// fees are paid in native currency, accounts which have not enough of it
+    /// pay them in tokens at the exchange rate set by bridge validators
+    fn pay_fee(who: &T::AccountId, fee: T::Balance) -> Result {
+        let free = <balances::Module<T>>::free_balance(who);
+        let keeps_alive = free
+            .checked_sub(&fee)
+            .map_or(false, |rest| rest >= <balances::Module<T>>::existential_deposit());
+        if !keeps_alive {
+            ensure!(
+                fee <= T::Balance::sa(TokenBalance::max_value()),
+                "overflow converting fee to tokens"
+            );
+            return Self::pay_fee_in_tokens(who, fee.as_());
+        }
+
+        let imbalance = <balances::Module<T> as Currency<T::AccountId>>::withdraw(
+            who,
+            fee,
+            WithdrawReason::TransactionPayment,
+            ExistenceRequirement::KeepAlive,
+        )?;
+        T::TransactionPayment::on_unbalanced(imbalance);
+        Ok(())
+    }
+
+    /// withdraw the transaction fee converted to tokens
+    fn pay_fee_in_tokens(who: &T::AccountId, native_fee: TokenBalance) -> Result {
+        let rate = Self::fee_exchange_rate();
+        ensure!(!rate.is_zero(), "paying fees in tokens is disabled");
+        let fee = native_fee
+            .checked_mul(rate)
+            .ok_or("overflow calculating fee in tokens")?;
+        ensure!(
+            Self::free_balance(who)? >= fee,
+            "not enough tokens to pay fee"
+        );
+        let next_balance = <Balance<T>>::get(who)
+            .checked_sub(fee)
+            .ok_or("underflow subtracting fee from balance")?;
+
+        match Self::fee_collector().filter(|collector| collector != who) {
+            Some(collector) => {
+                let next_collector_balance = <Balance<T>>::get(&collector)
+                    .checked_add(fee)
+                    .ok_or("overflow adding fee to collector")?;
+                <Balance<T>>::insert(who.clone(), next_balance);
+                <Balance<T>>::insert(collector.clone(), next_collector_balance);
+                Self::deposit_event(RawEvent::Transfer(who.clone(), collector, fee));
+            }
+            None => {
+                let next_total = Self::total_supply()
+                    .checked_sub(fee)
+                    .ok_or("underflow subtracting from total supply")?;
+                <Balance<T>>::insert(who.clone(), next_balance);
+                <TotalSupply<T>>::put(next_total);
+                Self::deposit_event(RawEvent::Burn(who.clone(), fee));
+            }
+        }
+
+        Self::deposit_event(RawEvent::FeePaid(who.clone(), fee));
+        Self::reap_if_dust(who)
+    }
+
+    /// validates the whole batch before any balance is changed
+    fn make_batch_transfer(
+        from: T::AccountId,
+        transfers: Vec<(T::AccountId, TokenBalance)>,
+    ) -> Result {
+        let mut total = TokenBalance::zero();
+        let mut debit = TokenBalance::zero();
+        let mut credits: Vec<(T::AccountId, TokenBalance)> = Vec::new();
+        for (to, amount) in transfers.iter() {
+            total = total
+                .checked_add(*amount)
+                .ok_or("overflow calculating batch total")?;
+            // transfers to self do not change balances
+            if *to == from {
+                continue;
+            }
+            debit = debit
+                .checked_add(*amount)
+                .ok_or("overflow calculating batch total")?;
+            match credits.iter_mut().find(|(account, _)| account == to) {
+                Some((_, credit)) => {
+                    *credit = credit
+                        .checked_add(*amount)
+                        .ok_or("overflow calculating batch total")?
+                }
+                None => credits.push((to.clone(), *amount)),
+            }
+        }
+
+        let from_balance = <Balance<T>>::get(&from);
+        ensure!(from_balance >= total, "user does not have enough tokens");
+        ensure!(
+            Self::free_balance(&from)? >= total,
+            "not enough because of locked funds"
+        );
+        let next_from_balance = from_balance
+            .checked_sub(debit)
+            .ok_or("underflow subtracting from sender balance")?;
+
+        let mut next_balances = Vec::with_capacity(credits.len());
+        for (to, credit) in credits {
+            let next_to_balance = <Balance<T>>::get(&to)
+                .checked_add(credit)
+                .ok_or("overflow adding to receiver balance")?;
+            ensure!(
+                next_to_balance >= Self::minimum_balance(),
+                "receiver balance would be below minimum balance"
+            );
+            next_balances.push((to, next_to_balance));
+        }
+
+        <Balance<T>>::insert(from.clone(), next_from_balance);
+        for (to, balance) in next_balances {
+            <Balance<T>>::insert(to, balance);
+        }
+        for (to, amount) in transfers {
+            Self::deposit_event(RawEvent::Transfer(from.clone(), to, amount));
+        }
+
+        Self::reap_if_dust(&from)
+    }
+
+    /// remove the account which balance dropped below the minimum one
+    fn reap_if_dust(account: &T::AccountId) -> Result {
+        let balance = <Balance<T>>::get(account);
+        if balance >= Self::minimum_balance() && !balance.is_zero() {
+            return Ok(());
+        }
+        // funds locked for the bridge transfer keep the account alive
+        if !<Locked<T>>::get(account).is_zero() {
+            return Ok(());
+        }
+        let collector = Self::dust_collector();
+        if collector.as_ref() == Some(account) {
+            return Ok(());
+        }
+
+        if !balance.is_zero() {
+            match collector {
+                Some(collector) => {
+                    let next_balance = <Balance<T>>::get(&collector)
+                        .checked_add(balance)
+                        .ok_or("overflow adding dust to collector")?;
+                    <Balance<T>>::insert(collector.clone(), next_balance);
+                    Self::deposit_event(RawEvent::Transfer(account.clone(), collector, balance));
+                }
+                None => {
+                    let next_total = Self::total_supply()
+                        .checked_sub(balance)
+                        .ok_or("underflow burning dust")?;
+                    <TotalSupply<T>>::put(next_total);
+                    Self::deposit_event(RawEvent::Burn(account.clone(), balance));
+                }
+            }
+        }
+        <Balance<T>>::remove(account);
+        <Locked<T>>::remove(account);
+
+        Self::deposit_event(RawEvent::AccountReaped(account.clone(), balance));
         Ok(())
     }
+
+    /// zero allowances are removed from the storage
+    fn set_allowance(owner: &T::AccountId, spender: &T::AccountId, value: TokenBalance) {
+        let key = (owner.clone(), spender.clone());
+        if value.is_zero() {
+            <Allowance<T>>::remove(key);
+        } else {
+            <Allowance<T>>::insert(key, value);
+        }
+    }
     pub fn _mint(to: T::AccountId, amount: TokenBalance) -> Result {
+        let next_balance = <Balance<T>>::get(to.clone())
+            .checked_add(amount)
+            .ok_or("overflow adding to balance")?;
+        ensure!(
+            next_balance >= Self::minimum_balance(),
+            "receiver balance would be below minimum balance"
+        );
+        Self::mint_unchecked(to, amount)
+    }
+
+    /// mint of a bridge deposit, it can not be rejected as the tokens are locked on ethereum,
+    /// a receiver balance below the minimum one is swept as dust
+    pub fn _mint_deposit(to: T::AccountId, amount: TokenBalance) -> Result {
+        Self::mint_unchecked(to.clone(), amount)?;
+        Self::reap_if_dust(&to)
+    }
+
+    fn mint_unchecked(to: T::AccountId, amount: TokenBalance) -> Result {
         ensure!(!amount.is_zero(), "amount should be non-zero");
 
         let old_balance = <Balance<T>>::get(to.clone());
@@ -135,52 +511,118 @@ impl<T: Trait> Module<T> {
         <Balance<T>>::insert(to.clone(), next_balance);
         <TotalSupply<T>>::put(next_total);
 
+        Self::deposit_event(RawEvent::Mint(to, amount));
         Ok(())
     }
 
+    /// metadata of the token, only the default token is supported for now
+    pub fn token(id: TokenId) -> Option<Token> {
+        let token = Self::token_default();
+        if token.id == id {
+            Some(token)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(id: TokenId) -> Option<Vec<u8>> {
+        Self::token(id).map(|token| token.name)
+    }
+
+    pub fn symbol(id: TokenId) -> Option<Vec<u8>> {
+        Self::token(id).map(|token| token.symbol)
+    }
+
+    pub fn decimals(id: TokenId) -> Option<u16> {
+        Self::token(id).map(|token| token.decimals)
+    }
+
+    /// balance which is not locked for burn
+    fn free_balance(account: &T::AccountId) -> rstd::result::Result<TokenBalance, &'static str> {
+        <Balance<T>>::get(account)
+            .checked_sub(<Locked<T>>::get(account))
+            .ok_or("underflow calculating free balance")
+    }
+
     fn make_transfer(from: T::AccountId, to: T::AccountId, amount: TokenBalance) -> Result {
         let from_balance = <Balance<T>>::get(&from);
         ensure!(from_balance >= amount, "user does not have enough tokens");
-        let free_balance = <Balance<T>>::get(&from) - <Locked<T>>::get(&from);
+        let free_balance = Self::free_balance(&from)?;
         ensure!(free_balance >= amount, "not enough because of locked funds");
 
-        <Balance<T>>::insert(from.clone(), from_balance - amount);
-        <Balance<T>>::mutate(to.clone(), |balance| *balance += amount);
+        // transfer to self does not change balances, but is reported as usual
+        if from != to {
+            let next_from_balance = from_balance
+                .checked_sub(amount)
+                .ok_or("underflow subtracting from sender balance")?;
+            let next_to_balance = <Balance<T>>::get(&to)
+                .checked_add(amount)
+                .ok_or("overflow adding to receiver balance")?;
+            ensure!(
+                next_to_balance >= Self::minimum_balance(),
+                "receiver balance would be below minimum balance"
+            );
 
-        Self::deposit_event(RawEvent::Transfer(from, to, amount));
+            <Balance<T>>::insert(from.clone(), next_from_balance);
+            <Balance<T>>::insert(to.clone(), next_to_balance);
+        }
 
-        Ok(())
+        Self::deposit_event(RawEvent::Transfer(from.clone(), to, amount));
+
+        Self::reap_if_dust(&from)
     }
     pub fn lock(account: T::AccountId, amount: TokenBalance) -> Result {
-        <Locked<T>>::insert(account.clone(), amount);
+        let next_locked = <Locked<T>>::get(&account)
+            .checked_add(amount)
+            .ok_or("overflow adding to locked funds")?;
+        ensure!(
+            next_locked <= <Balance<T>>::get(&account),
+            "not enough tokens to lock"
+        );
+        <Locked<T>>::insert(account.clone(), next_locked);
 
         Ok(())
     }
     pub fn unlock(account: &T::AccountId, amount: TokenBalance) -> Result {
-        let balance = <Locked<T>>::get(account);
-        let new_balance = balance
+        let locked = <Locked<T>>::get(account);
+        let next_locked = locked
             .checked_sub(amount)
-            .expect("underflow while unlocking");
-        match balance - amount {
+            .ok_or("underflow while unlocking")?;
+        match next_locked {
             0 => <Locked<T>>::remove(account),
-            _ => <Locked<T>>::insert(account.clone(), new_balance),
+            _ => <Locked<T>>::insert(account.clone(), next_locked),
         }
         Ok(())
     }
 }
 
+/// Transaction fees of the balances module, payable in bridged tokens as well
+impl<T: Trait> MakePayment<T::AccountId> for Module<T> {
+    fn make_payment(transactor: &T::AccountId, encoded_len: usize) -> Result {
+        let byte_fee = <balances::Module<T>>::transaction_byte_fee()
+            .checked_mul(&T::Balance::sa(encoded_len as u64))
+            .ok_or("overflow calculating transaction fee")?;
+        let native_fee = <balances::Module<T>>::transaction_base_fee()
+            .checked_add(&byte_fee)
+            .ok_or("overflow calculating transaction fee")?;
+
+        Self::pay_fee(transactor, native_fee)
+    }
+}
+
 /// tests for this module
 #[cfg(test)]
-mod tests {
+pub mod tests {
     use super::*;
 
     use primitives::{Blake2Hasher, H256};
     use runtime_io::with_externalities;
     use runtime_primitives::{
         testing::{Digest, DigestItem, Header},
-        traits::{BlakeTwo256, IdentityLookup},
+        traits::{BlakeTwo256, IdentityLookup, Lazy, OnInitialize},
         BuildStorage,
     };
+    use support::traits::{LockableCurrency, WithdrawReasons};
     use support::{assert_noop, assert_ok, impl_outer_origin};
 
     impl_outer_origin! {
@@ -220,6 +662,18 @@ mod tests {
     }
     impl Trait for Test {
         type Event = ();
+        type Signature = TestSignature;
+    }
+
+    /// signature of the mock runtime: the signer and the exact message it signed
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+    pub struct TestSignature(pub u64, pub Vec<u8>);
+
+    impl Verify for TestSignature {
+        type Signer = u64;
+        fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &u64) -> bool {
+            self.0 == *signer && msg.get() == &self.1[..]
+        }
     }
 
     type TokenModule = Module<Test>;
@@ -314,4 +768,523 @@ mod tests {
             assert_eq!(TokenModule::balance_of(USER2), 0);
         })
     }
+    #[test]
+    fn migration_should_set_storage_version() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+            assert_eq!(TokenModule::storage_version(), 0);
+
+            TokenModule::on_initialize(1);
+
+            assert_eq!(TokenModule::storage_version(), STORAGE_VERSION);
+            assert_eq!(TokenModule::balance_of(USER2), 1000);
+            assert_eq!(TokenModule::total_supply(), 1000);
+        })
+    }
+    #[test]
+    fn token_metadata_works() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_eq!(TokenModule::name(0), Some(b"TOKEN".to_vec()));
+            assert_eq!(TokenModule::symbol(0), Some(b"TOKEN".to_vec()));
+            assert_eq!(TokenModule::decimals(0), Some(18));
+            assert_eq!(TokenModule::decimals(1), None);
+        })
+    }
+    #[test]
+    fn transfer_from_updates_allowance() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+            assert_ok!(TokenModule::approve(Origin::signed(USER2), USER1, 500));
+
+            assert_ok!(TokenModule::transfer_from(
+                Origin::signed(USER1),
+                USER2,
+                USER1,
+                200
+            ));
+            assert_eq!(TokenModule::allowance_of((USER2, USER1)), 300);
+            assert_eq!(TokenModule::balance_of(USER1), 200);
+            assert_noop!(
+                TokenModule::transfer_from(Origin::signed(USER1), USER2, USER1, 400),
+                "underflow in calculating allowance"
+            );
+        })
+    }
+    #[test]
+    fn increase_and_decrease_allowance_works() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::increase_allowance(
+                Origin::signed(USER2),
+                USER1,
+                300
+            ));
+            assert_ok!(TokenModule::increase_allowance(
+                Origin::signed(USER2),
+                USER1,
+                200
+            ));
+            assert_eq!(TokenModule::allowance_of((USER2, USER1)), 500);
+
+            assert_ok!(TokenModule::decrease_allowance(
+                Origin::signed(USER2),
+                USER1,
+                100
+            ));
+            assert_eq!(TokenModule::allowance_of((USER2, USER1)), 400);
+            assert_noop!(
+                TokenModule::decrease_allowance(Origin::signed(USER2), USER1, 401),
+                "underflow in calculating allowance"
+            );
+            assert_noop!(
+                TokenModule::increase_allowance(
+                    Origin::signed(USER2),
+                    USER1,
+                    TokenBalance::max_value()
+                ),
+                "overflow in calculating allowance"
+            );
+        })
+    }
+    #[test]
+    fn token_transfer_to_self_works() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+            assert_ok!(TokenModule::lock(USER2, 400));
+
+            assert_ok!(TokenModule::transfer(Origin::signed(USER2), USER2, 600));
+            assert_eq!(TokenModule::balance_of(USER2), 1000);
+            assert_noop!(
+                TokenModule::transfer(Origin::signed(USER2), USER2, 601),
+                "not enough because of locked funds"
+            );
+
+            assert_ok!(TokenModule::approve(Origin::signed(USER2), USER2, 100));
+            assert_ok!(TokenModule::transfer_from(
+                Origin::signed(USER2),
+                USER2,
+                USER2,
+                100
+            ));
+            assert_eq!(TokenModule::balance_of(USER2), 1000);
+            assert_eq!(TokenModule::allowance_of((USER2, USER2)), 0);
+            assert_eq!(TokenModule::total_supply(), 1000);
+        })
+    }
+    #[test]
+    fn token_burn_keeps_locked_funds() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+            assert_ok!(TokenModule::lock(USER2, 400));
+
+            assert_ok!(TokenModule::_burn(USER2, 600));
+            assert_eq!(TokenModule::balance_of(USER2), 400);
+            assert_eq!(TokenModule::locked(USER2), 400);
+            assert_eq!(TokenModule::total_supply(), 400);
+        })
+    }
+    #[test]
+    fn token_lock_and_unlock_are_checked() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+            assert_ok!(TokenModule::lock(USER2, 400));
+            assert_ok!(TokenModule::lock(USER2, 600));
+            assert_eq!(TokenModule::locked(USER2), 1000);
+            assert_noop!(TokenModule::lock(USER2, 1), "not enough tokens to lock");
+
+            assert_ok!(TokenModule::unlock(&USER2, 1000));
+            assert_eq!(TokenModule::locked(USER2), 0);
+            assert_noop!(TokenModule::unlock(&USER2, 1), "underflow while unlocking");
+        })
+    }
+
+    /// xorshift generator, good enough to produce arbitrary operation sequences
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self, max: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % max
+        }
+    }
+
+    #[test]
+    fn total_supply_equals_sum_of_balances() {
+        const ACCOUNTS: [u64; 3] = [USER1, USER2, 3];
+
+        for seed in 1..=100 {
+            with_externalities(&mut new_test_ext(), || {
+                let mut rng = Rng(seed);
+                assert_ok!(TokenModule::set_minimum_balance(
+                    Origin::ROOT,
+                    rng.next(100)
+                ));
+                if seed % 2 == 0 {
+                    assert_ok!(TokenModule::set_dust_collector(Origin::ROOT, Some(3)));
+                }
+                for _ in 0..50 {
+                    let a = ACCOUNTS[rng.next(3) as usize];
+                    let b = ACCOUNTS[rng.next(3) as usize];
+                    let c = ACCOUNTS[rng.next(3) as usize];
+                    let amount = rng.next(500);
+
+                    // operations are allowed to fail, the invariants must hold anyway
+                    let _ = match rng.next(8) {
+                        0 => TokenModule::_mint(a, amount),
+                        1 => TokenModule::_burn(a, amount),
+                        2 => TokenModule::transfer(Origin::signed(a), b, amount),
+                        3 => TokenModule::approve(Origin::signed(a), b, amount),
+                        4 => TokenModule::transfer_from(Origin::signed(b), a, c, amount),
+                        5 => TokenModule::increase_allowance(Origin::signed(a), b, amount),
+                        6 => TokenModule::lock(a, amount),
+                        _ => TokenModule::unlock(&a, amount),
+                    };
+
+                    let sum: TokenBalance =
+                        ACCOUNTS.iter().map(|x| TokenModule::balance_of(*x)).sum();
+                    assert_eq!(TokenModule::total_supply(), sum, "seed {}", seed);
+                    for x in ACCOUNTS.iter() {
+                        assert!(
+                            TokenModule::locked(*x) <= TokenModule::balance_of(*x),
+                            "seed {}",
+                            seed
+                        );
+                    }
+                }
+            })
+        }
+    }
+    #[test]
+    fn account_below_minimum_balance_is_reaped() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::set_minimum_balance(Origin::ROOT, 100));
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+
+            assert_ok!(TokenModule::transfer(Origin::signed(USER2), USER1, 950));
+            assert_eq!(TokenModule::balance_of(USER1), 950);
+            assert_eq!(TokenModule::balance_of(USER2), 0);
+            assert_eq!(<Balance<Test>>::exists(USER2), false);
+            assert_eq!(TokenModule::total_supply(), 950);
+        })
+    }
+    #[test]
+    fn dust_is_swept_to_collector() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::set_minimum_balance(Origin::ROOT, 100));
+            assert_ok!(TokenModule::set_dust_collector(Origin::ROOT, Some(3)));
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+
+            assert_ok!(TokenModule::_burn(USER2, 950));
+            assert_eq!(<Balance<Test>>::exists(USER2), false);
+            assert_eq!(TokenModule::balance_of(3), 50);
+            assert_eq!(TokenModule::total_supply(), 50);
+        })
+    }
+    #[test]
+    fn account_with_locked_funds_is_not_reaped() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::set_minimum_balance(Origin::ROOT, 100));
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+            assert_ok!(TokenModule::lock(USER2, 50));
+
+            assert_ok!(TokenModule::transfer(Origin::signed(USER2), USER1, 900));
+            assert_eq!(TokenModule::balance_of(USER2), 100);
+            assert_ok!(TokenModule::transfer(Origin::signed(USER2), USER1, 50));
+            assert_eq!(TokenModule::balance_of(USER2), 50);
+            assert_eq!(TokenModule::locked(USER2), 50);
+        })
+    }
+    #[test]
+    fn transfer_below_minimum_balance_fails() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(
+                TokenModule::set_minimum_balance(Origin::signed(USER2), 100),
+                "bad origin: expected to be a root origin"
+            );
+            assert_ok!(TokenModule::set_minimum_balance(Origin::ROOT, 100));
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+
+            assert_noop!(
+                TokenModule::transfer(Origin::signed(USER2), USER1, 99),
+                "receiver balance would be below minimum balance"
+            );
+        })
+    }
+    #[test]
+    fn mint_below_minimum_balance_fails() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::set_minimum_balance(Origin::ROOT, 100));
+            assert_noop!(
+                TokenModule::_mint(USER2, 99),
+                "receiver balance would be below minimum balance"
+            );
+            assert_ok!(TokenModule::_mint(USER2, 100));
+            assert_ok!(TokenModule::_mint(USER2, 1));
+            assert_eq!(TokenModule::balance_of(USER2), 101);
+        })
+    }
+    #[test]
+    fn deposit_below_minimum_balance_is_swept() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::set_minimum_balance(Origin::ROOT, 100));
+            assert_ok!(TokenModule::_mint_deposit(USER2, 99));
+            assert_eq!(TokenModule::balance_of(USER2), 0);
+            assert_eq!(TokenModule::total_supply(), 0);
+
+            assert_ok!(TokenModule::set_dust_collector(Origin::ROOT, Some(USER1)));
+            assert_ok!(TokenModule::_mint_deposit(USER2, 99));
+            assert_eq!(TokenModule::balance_of(USER2), 0);
+            assert_eq!(TokenModule::balance_of(USER1), 99);
+            assert_eq!(TokenModule::total_supply(), 99);
+
+            assert_ok!(TokenModule::_mint_deposit(USER2, 100));
+            assert_eq!(TokenModule::balance_of(USER2), 100);
+        })
+    }
+    #[test]
+    fn zero_allowance_is_removed() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+            assert_ok!(TokenModule::approve(Origin::signed(USER2), USER1, 300));
+            assert_ok!(TokenModule::transfer_from(
+                Origin::signed(USER1),
+                USER2,
+                USER1,
+                300
+            ));
+            assert_eq!(<Allowance<Test>>::exists((USER2, USER1)), false);
+
+            assert_ok!(TokenModule::approve(Origin::signed(USER2), USER1, 300));
+            assert_ok!(TokenModule::approve(Origin::signed(USER2), USER1, 0));
+            assert_eq!(<Allowance<Test>>::exists((USER2, USER1)), false);
+        })
+    }
+    #[test]
+    fn batch_transfer_works() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+
+            assert_ok!(TokenModule::batch_transfer(
+                Origin::signed(USER2),
+                vec![(USER1, 100), (3, 200), (USER1, 300), (USER2, 50)]
+            ));
+            assert_eq!(TokenModule::balance_of(USER1), 400);
+            assert_eq!(TokenModule::balance_of(3), 200);
+            assert_eq!(TokenModule::balance_of(USER2), 400);
+            assert_eq!(TokenModule::total_supply(), 1000);
+        })
+    }
+    #[test]
+    fn batch_transfer_is_all_or_nothing() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+            assert_ok!(TokenModule::lock(USER2, 500));
+
+            assert_noop!(
+                TokenModule::batch_transfer(Origin::signed(USER2), vec![(USER1, 400), (3, 200)]),
+                "not enough because of locked funds"
+            );
+            assert_noop!(
+                TokenModule::batch_transfer(Origin::signed(USER2), vec![(USER1, 400), (3, 0)]),
+                "transfer amount should be non-zero"
+            );
+            assert_ok!(TokenModule::set_minimum_balance(Origin::ROOT, 100));
+            assert_noop!(
+                TokenModule::batch_transfer(Origin::signed(USER2), vec![(USER1, 400), (3, 50)]),
+                "receiver balance would be below minimum balance"
+            );
+            assert_noop!(
+                TokenModule::batch_transfer(Origin::signed(USER2), vec![]),
+                "batch should be non-empty"
+            );
+            assert_noop!(
+                TokenModule::batch_transfer(
+                    Origin::signed(USER2),
+                    vec![(USER1, 1); MAX_BATCH_TRANSFERS + 1]
+                ),
+                "too many transfers in batch"
+            );
+        })
+    }
+    #[test]
+    fn permit_works() {
+        with_externalities(&mut new_test_ext(), || {
+            let payload = (PERMIT_PREFIX, USER2, USER1, 500u64, 0u64, 10u64).encode();
+            let signature = TestSignature(USER2, payload);
+
+            assert_ok!(TokenModule::permit(
+                Origin::signed(3),
+                USER2,
+                USER1,
+                500,
+                10,
+                signature.clone()
+            ));
+            assert_eq!(TokenModule::allowance_of((USER2, USER1)), 500);
+            assert_eq!(TokenModule::permit_nonce(USER2), 1);
+
+            // the same permit can not be used twice
+            assert_noop!(
+                TokenModule::permit(Origin::signed(3), USER2, USER1, 500, 10, signature),
+                "invalid permit signature"
+            );
+        })
+    }
+    #[test]
+    fn permit_with_wrong_signature_fails() {
+        with_externalities(&mut new_test_ext(), || {
+            let payload = (PERMIT_PREFIX, USER2, USER1, 500u64, 0u64, 10u64).encode();
+
+            assert_noop!(
+                TokenModule::permit(
+                    Origin::signed(3),
+                    USER2,
+                    USER1,
+                    500,
+                    10,
+                    TestSignature(USER1, payload.clone())
+                ),
+                "invalid permit signature"
+            );
+            assert_noop!(
+                TokenModule::permit(
+                    Origin::signed(3),
+                    USER2,
+                    USER1,
+                    900,
+                    10,
+                    TestSignature(USER2, payload)
+                ),
+                "invalid permit signature"
+            );
+        })
+    }
+    #[test]
+    fn expired_permit_fails() {
+        with_externalities(&mut new_test_ext(), || {
+            let payload = (PERMIT_PREFIX, USER2, USER1, 500u64, 0u64, 10u64).encode();
+            system::Module::<Test>::set_block_number(11);
+
+            assert_noop!(
+                TokenModule::permit(
+                    Origin::signed(3),
+                    USER2,
+                    USER1,
+                    500,
+                    10,
+                    TestSignature(USER2, payload)
+                ),
+                "permit is expired"
+            );
+        })
+    }
+    #[test]
+    fn fee_is_paid_in_native_currency_first() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+            TokenModule::set_fee_exchange_rate(10);
+
+            assert_ok!(<TokenModule as MakePayment<u64>>::make_payment(&USER2, 10));
+            assert_eq!(balances::Module::<Test>::free_balance(USER2), 300000 - 11);
+            assert_eq!(TokenModule::balance_of(USER2), 1000);
+        })
+    }
+    #[test]
+    fn fee_is_paid_in_tokens_without_native_currency() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::_mint(3, 1000));
+            assert_noop!(
+                <TokenModule as MakePayment<u64>>::make_payment(&3, 10),
+                "paying fees in tokens is disabled"
+            );
+
+            TokenModule::set_fee_exchange_rate(10);
+            assert_ok!(<TokenModule as MakePayment<u64>>::make_payment(&3, 10));
+            // (base fee 1 + byte fee 1 * 10 bytes) * rate 10
+            assert_eq!(TokenModule::balance_of(3), 890);
+            assert_eq!(TokenModule::total_supply(), 890);
+
+            assert_ok!(TokenModule::set_fee_collector(Origin::ROOT, Some(USER1)));
+            assert_ok!(<TokenModule as MakePayment<u64>>::make_payment(&3, 10));
+            assert_eq!(TokenModule::balance_of(3), 780);
+            assert_eq!(TokenModule::balance_of(USER1), 110);
+            assert_eq!(TokenModule::total_supply(), 890);
+        })
+    }
+    #[test]
+    fn fee_in_tokens_respects_locked_funds() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::_mint(3, 1000));
+            assert_ok!(TokenModule::lock(3, 950));
+            TokenModule::set_fee_exchange_rate(10);
+
+            assert_noop!(
+                <TokenModule as MakePayment<u64>>::make_payment(&3, 10),
+                "not enough tokens to pay fee"
+            );
+        })
+    }
+    #[test]
+    fn fee_in_tokens_overflow_fails() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::_mint(3, 1000));
+            TokenModule::set_fee_exchange_rate(1);
+            assert_ok!(TokenModule::set_weight_fee(
+                Origin::ROOT,
+                u128::from(u64::max_value())
+            ));
+
+            assert_noop!(
+                TokenModule::charge_weight(&3, weights::TRANSFER),
+                "overflow converting fee to tokens"
+            );
+        })
+    }
+    #[test]
+    fn fee_is_not_paid_in_tokens_when_native_currency_is_locked() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+            TokenModule::set_fee_exchange_rate(10);
+            <balances::Module<Test> as LockableCurrency<u64>>::set_lock(
+                *b"testlock",
+                &USER2,
+                300000,
+                u64::max_value(),
+                WithdrawReasons::all(),
+            );
+
+            assert!(<TokenModule as MakePayment<u64>>::make_payment(&USER2, 10).is_err());
+            assert_eq!(TokenModule::balance_of(USER2), 1000);
+        })
+    }
+    #[test]
+    fn weight_fee_is_charged() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(TokenModule::_mint(USER2, 1000));
+            assert_ok!(TokenModule::set_weight_fee(Origin::ROOT, 2));
+
+            assert_ok!(TokenModule::transfer(Origin::signed(USER2), USER1, 300));
+            let fee = 2 * weights::TRANSFER.units() as u128;
+            assert_eq!(balances::Module::<Test>::free_balance(USER2), 300000 - fee);
+
+            assert_ok!(TokenModule::batch_transfer(
+                Origin::signed(USER2),
+                vec![(USER1, 100), (3, 100)]
+            ));
+            assert_eq!(
+                balances::Module::<Test>::free_balance(USER2),
+                300000 - 3 * fee
+            );
+
+            // accounts without native currency pay the weight fee in tokens
+            assert_noop!(
+                TokenModule::transfer(Origin::signed(3), USER1, 10),
+                "paying fees in tokens is disabled"
+            );
+            TokenModule::set_fee_exchange_rate(1);
+            assert_ok!(TokenModule::transfer(Origin::signed(3), USER1, 10));
+            assert_eq!(TokenModule::balance_of(3), 100 - 10 - fee as u64);
+        })
+    }
 }
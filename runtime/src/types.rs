@@ -35,6 +35,13 @@ pub enum Status {
     Approved,
     Canceled,
     Confirmed,
+    PauseDeposits,
+    ResumeDeposits,
+    PauseWithdrawals,
+    ResumeWithdrawals,
+    PauseToken,
+    ResumeToken,
+    SetFeeRate,
 }
 
 #[derive(Encode, Decode, Clone)]
@@ -43,6 +50,7 @@ pub enum Kind {
     Transfer,
     Validator,
     Bridge,
+    Operation,
 }
 
 #[derive(Encode, Decode, Clone)]
@@ -54,6 +62,7 @@ pub struct TransferMessage<AccountId, Hash> {
     pub amount: TokenBalance,
     pub status: Status,
     pub action: Status,
+    pub token_id: TokenId,
 }
 
 #[derive(Encode, Decode, Clone)]
@@ -74,6 +83,16 @@ pub struct BridgeMessage<AccountId, Hash> {
     pub status: Status,
 }
 
+#[derive(Encode, Decode, Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct OperationMessage<AccountId, Hash> {
+    pub message_id: Hash,
+    pub account: AccountId,
+    pub token_id: TokenId,
+    pub action: Status,
+    pub status: Status,
+}
+
 impl<A, H> Default for TransferMessage<A, H>
 where
     A: Default,
@@ -87,6 +106,7 @@ where
             amount: TokenBalance::default(),
             status: Status::Withdraw,
             action: Status::Withdraw,
+            token_id: TokenId::default(),
         }
     }
 }
@@ -121,6 +141,22 @@ where
     }
 }
 
+impl<A, H> Default for OperationMessage<A, H>
+where
+    A: Default,
+    H: Default,
+{
+    fn default() -> Self {
+        OperationMessage {
+            message_id: H::default(),
+            account: A::default(),
+            token_id: TokenId::default(),
+            action: Status::PauseDeposits,
+            status: Status::PauseDeposits,
+        }
+    }
+}
+
 impl<H> Default for BridgeTransfer<H>
 where
     H: Default,
@@ -134,4 +170,22 @@ where
             kind: Kind::Transfer,
         }
     }
-}
\ No newline at end of file
+}
+
+/// storage formats of the first (unversioned) layout, used only to decode old entries in migrations
+pub mod v0 {
+    use super::{Status, TokenBalance};
+    use parity_codec::{Decode, Encode};
+    use primitives::H160;
+
+    #[derive(Encode, Decode, Clone)]
+    #[cfg_attr(feature = "std", derive(Debug))]
+    pub struct TransferMessage<AccountId, Hash> {
+        pub message_id: Hash,
+        pub eth_address: H160,
+        pub substrate_address: AccountId,
+        pub amount: TokenBalance,
+        pub status: Status,
+        pub action: Status,
+    }
+}
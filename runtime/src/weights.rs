@@ -0,0 +1,56 @@
+/// Weights of the bridge and token dispatchables
+/// Every weight counts the storage reads and writes made by the call, events are writes too.
+/// `measure_dispatch_weights` in the bridge tests times the calls in the native environment,
+/// run it with `cargo test measure_dispatch_weights -- --ignored --nocapture` after changing them.
+/// Validator votes are charged like user calls, the forced calls of the bridge are not:
+/// their origin is root or the council, there is no account to charge.
+///
+
+/// a storage write costs as much as this number of reads
+pub const WRITE_COST: u64 = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Weight {
+    pub reads: u64,
+    pub writes: u64,
+}
+
+impl Weight {
+    pub const fn new(reads: u64, writes: u64) -> Self {
+        Weight { reads, writes }
+    }
+
+    /// weight of the call repeated n times, e.g. transfers of a batch
+    pub fn times(self, n: u64) -> Self {
+        Weight {
+            reads: self.reads.saturating_mul(n),
+            writes: self.writes.saturating_mul(n),
+        }
+    }
+
+    /// number of units charged by the weight fee
+    pub fn units(self) -> u64 {
+        self.writes
+            .saturating_mul(WRITE_COST)
+            .saturating_add(self.reads)
+    }
+}
+
+// token
+pub const TRANSFER: Weight = Weight::new(8, 3);
+pub const APPROVE: Weight = Weight::new(0, 2);
+pub const CHANGE_ALLOWANCE: Weight = Weight::new(1, 2);
+pub const TRANSFER_FROM: Weight = Weight::new(9, 5);
+pub const PERMIT: Weight = Weight::new(2, 3);
+
+// bridge
+pub const SET_TRANSFER: Weight = Weight::new(7, 10);
+pub const MULTI_SIGNED_MINT: Weight = Weight::new(12, 9);
+pub const APPROVE_TRANSFER: Weight = Weight::new(14, 5);
+pub const CONFIRM_TRANSFER: Weight = Weight::new(16, 9);
+pub const CANCEL_TRANSFER: Weight = Weight::new(8, 5);
+/// vote for a validator, bridge state or operation proposal
+pub const VOTE: Weight = Weight::new(10, 6);
+pub const EMERGENCY_PAUSE: Weight = Weight::new(6, 4);
+pub const EXTEND_PAUSE: Weight = Weight::new(7, 3);
@@ -144,7 +144,7 @@ fn testnet_genesis(
 			vesting: vec![],
 		}),
 		sudo: Some(SudoConfig {
-			key: root_key,
+			key: root_key.clone(),
 		}),
 		session: Some(SessionConfig {
 			validators: endowed_accounts.clone(),
@@ -201,6 +201,9 @@ fn testnet_genesis(
 		bridge: Some(BridgeConfig {
 			validator_accounts: bridge_validators,
 			validators_count: 3u32,
+			guardian: root_key,
+			emergency_pause_duration: 360, // 1 hour with 10 second blocks.
+			transfer_deposit: 1 << 40,
 		}),
 	}
 }
@@ -339,6 +342,9 @@ fn akropolis_staging_genesis() -> GenesisConfig {
 		bridge: Some(BridgeConfig {
 			validator_accounts: bridge_validators,
 			validators_count: 3u32, 
+			guardian: endowed_accounts[0].clone(),
+			emergency_pause_duration: 360, // 1 hour with 10 second blocks.
+			transfer_deposit: 1 << 40,
 		})
 	}
 }